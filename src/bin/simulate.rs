@@ -0,0 +1,112 @@
+//! Play a batch of seeded games with one of the built-in strategies and report win rate, score
+//! and per-monster statistics.
+//!
+//! Usage: simulate [--games N] [--seed FIRST_SEED] [--strategy NAME] [--difficulty NAME]
+//!                 [--dungeon SIZE] [--payments N] [--reshuffles N] [--solve POSITIONS]
+//!                 [--format text|csv|json]
+//!
+//! With `--solve`, every seed is also searched by the solver for up to `POSITIONS` positions,
+//! reporting how many seeds can be won and the best score found.
+
+use maverick::consts::*;
+use maverick::rules::{Difficulty, Rules, DIFFICULTIES};
+use maverick::simulate::simulate;
+
+/// Output formats of the report
+#[derive(Debug, Copy, Clone)]
+enum Format {
+    Text,
+    Csv,
+    Json
+}
+
+fn main() {
+    let mut games = 100;
+    let mut first_seed = 0;
+    let mut strategy = "greedy".to_string();
+    let mut difficulty = Difficulty::Normal;
+    let mut dungeon_size = None;
+    let mut payments = None;
+    let mut reshuffles = 0;
+    let mut solve_limit = None;
+    let mut format = Format::Text;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let value = args.next();
+        let parsed = match (arg.as_str(), value.as_deref()) {
+            ("--games", Some(value)) => value.parse().map(|value| games = value).is_ok(),
+            ("--seed", Some(value)) => value.parse().map(|value| first_seed = value).is_ok(),
+            ("--dungeon", Some(value)) => value.parse().map(|value| dungeon_size = Some(value)).is_ok(),
+            ("--payments", Some(value)) => value.parse().map(|value| payments = Some(value)).is_ok(),
+            ("--reshuffles", Some(value)) => value.parse().map(|value| reshuffles = value).is_ok(),
+            ("--solve", Some(value)) => value.parse().map(|value| solve_limit = Some(value)).is_ok(),
+            ("--difficulty", Some(value)) => match Difficulty::by_name(value) {
+                Some(value) => {
+                    difficulty = value;
+                    true
+                }
+                None => {
+                    eprintln!("Unknown difficulty {}, expected one of {:?}", value, DIFFICULTIES);
+                    false
+                }
+            }
+            ("--strategy", Some(value)) => {
+                strategy = value.to_string();
+                true
+            }
+            ("--format", Some("text")) => { format = Format::Text; true }
+            ("--format", Some("csv")) => { format = Format::Csv; true }
+            ("--format", Some("json")) => { format = Format::Json; true }
+            _ => false
+        };
+
+        if !parsed {
+            eprintln!("Invalid argument {} {:?}", arg, value);
+            eprintln!("Usage: simulate [--games N] [--seed FIRST_SEED] [--strategy NAME] \
+                       [--difficulty NAME] [--dungeon SIZE] [--payments N] [--reshuffles N] \
+                       [--solve POSITIONS] [--format text|csv|json]");
+            std::process::exit(1);
+        }
+    }
+
+    // Play with the same monsters and deck as the game itself. The dungeon size and payments
+    // of the difficulty can be overridden, and the discard pile can be reshuffled into the deck.
+    let rules = Rules::load(ASSET_DIR)
+        .and_then(|rules| rules.with_difficulty(difficulty))
+        .and_then(|rules| match dungeon_size {
+            Some(size) => rules.with_dungeon_size(size),
+            None => Ok(rules)
+        })
+        .and_then(|rules| match payments {
+            Some(payments) => rules.with_payments(payments),
+            None => Ok(rules)
+        });
+    let rules = match rules {
+        Ok(rules) => Rules { reshuffles, ..rules },
+        Err(e) => {
+            eprintln!("Failed to load rules: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let report = match simulate(&strategy, &rules, first_seed, games, solve_limit) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match format {
+        Format::Text => print!("{}", report.to_text()),
+        Format::Csv => print!("{}", report.to_csv()),
+        Format::Json => match report.to_json() {
+            Ok(json) => println!("{}", json),
+            Err(e) => {
+                eprintln!("Failed to write JSON: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
@@ -0,0 +1,347 @@
+//! Play Maverick in a terminal. The board is drawn as text after every command and commands are
+//! typed at a prompt, so a game can be played over SSH. Games use the same rules, seeds and
+//! replays as the windowed game, so the same commands always give the same score.
+//!
+//! Usage: tui [SEED] [--difficulty NAME] [--dungeon SIZE] [--strict] [--ironman]
+
+use std::io::{self, BufRead, Write};
+
+use maverick::consts::*;
+use maverick::engine::*;
+use maverick::history::History;
+use maverick::moves::legal_moves;
+use maverick::replay::Replay;
+use maverick::rules::{Difficulty, Rules, DIFFICULTIES};
+use maverick::scores::ScoreTable;
+use maverick::script::parse_command;
+use maverick::stats::{GameEvent, LifetimeStats};
+
+/// Width of every column of the board, one per slot of the dungeon row
+const SLOT_WIDTH: usize = 8;
+
+/// Width of the row labels on the left of the board
+const LABEL_WIDTH: usize = 9;
+
+const HELP: &str = "\
+Commands:
+  N move left|right         move the character with card N
+  N range left|right        fire a Range attack from the character with card N
+  N melee                   attack the monster under the character with card N
+  N c move|range|melee ...  the same for the companion
+  N swap                    swap the companion to the other kind
+  discard N                 lose card N to a Noxious monster
+  end                       end the turn and refill the hand
+  hint                      list every useful play and what it would do
+  undo, redo                take back or play again a card played this turn
+  stats                     show the statistics over every game played
+  help, quit
+Directions can be shortened to l and r. Hits: M = Move, R = Range, X = Melee";
+
+/// One row of the board: a label followed by a cell for every slot of the dungeon row
+fn row(label: &str, cells: impl Iterator<Item = String>) -> String {
+    let mut line = format!("{:<width$}", label, width = LABEL_WIDTH);
+    for cell in cells {
+        let cell: String = cell.chars().take(SLOT_WIDTH - 1).collect();
+        line.push_str(&format!("|{:<width$}", cell, width = SLOT_WIDTH - 1));
+    }
+
+    line.push('|');
+    line
+}
+
+/// Draw the three rows of the board, the hand and the game information as text
+fn render(game: &GameState) -> String {
+    let monsters = &game.monsters;
+    let slots = 0..game.dungeon_size();
+    let mut lines = Vec::new();
+
+    lines.push(row("slot", slots.clone().map(|index| index.to_string())));
+
+    // The character above the dungeon row
+    lines.push(row("hero", slots.clone().map(|index| {
+        match (index == game.player_index, game.player_kind) {
+            (true, PlayerKind::Regular) => "@".to_string(),
+            (true, PlayerKind::Monstrous) => "Monster".to_string(),
+            (false, _) => String::new(),
+        }
+    })));
+
+    lines.push(row("", slots.clone().map(|_| "-".repeat(SLOT_WIDTH - 1))));
+    lines.push(row("monster", slots.clone().map(|index| monsters.names[index].clone())));
+
+    // The deck has no stats
+    let alive = |index: usize| index != DECK_SLOT && monsters.alive[index];
+    lines.push(row("strength", slots.clone().map(|index| {
+        let adjustment = monsters.strength_adjustments[index];
+        match (alive(index), adjustment) {
+            (false, _) if index != DECK_SLOT => "slain".to_string(),
+            (false, _) => String::new(),
+            (true, 0) => monsters.strengths[index].to_string(),
+            (true, _) => format!("{}+{}", monsters.strengths[index], adjustment),
+        }
+    })));
+    lines.push(row("needs", slots.clone().map(|index| match alive(index) {
+        true => monsters.remaining_hits(index).iter().map(ToSlay::symbol).collect(),
+        false => String::new(),
+    })));
+    lines.push(row("hits", slots.clone().map(|index| {
+        monsters.current_hits[index].iter().map(ToSlay::symbol).collect()
+    })));
+    lines.push(row("ability", slots.clone().map(|index| {
+        match monsters.abilities[index] {
+            Some(_) if !alive(index) => String::new(),
+            Some(Ability::Reign) if monsters.reign(index) => "Reign!".to_string(),
+            Some(ability) => format!("{:?}", ability),
+            None => String::new(),
+        }
+    })));
+    lines.push(row("", slots.clone().map(|_| "-".repeat(SLOT_WIDTH - 1))));
+
+    // The companion below the dungeon row
+    lines.push(row("comp", slots.map(|index| match index == game.companion_index {
+        true => format!("{:?}", game.companion_kind),
+        false => String::new(),
+    })));
+
+    let hand: Vec<String> = game.hand.iter().enumerate()
+        .map(|(i, card)| format!("{}:[{}]", i + 1, card))
+        .collect();
+
+    lines.push(String::new());
+    lines.push(format!("Hand: {}", hand.join(" ")));
+    lines.push(format!("Turn: {}  Deck left: {}  Trophies: {}  Score: {}  Difficulty: {}",
+                       game.turn, game.deck.len(), game.trophies, game.score(), game.difficulty));
+
+    if game.discard_pending {
+        lines.push("Noxious! Choose a card to discard with: discard N".to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// Describe what happened when a command was applied
+fn describe(game: &GameState, outcome: &Outcome) -> String {
+    let mut parts = Vec::new();
+
+    if let Some(index) = outcome.target {
+        match outcome.hit {
+            Some(hit) => parts.push(format!("{:?} hit on {}", hit, game.monsters.names[index])),
+            None => parts.push(format!("reached {}", game.monsters.names[index])),
+        }
+    }
+    if outcome.trophy {
+        parts.push("trophy earned".to_string());
+    }
+    if outcome.killed {
+        parts.push("slain!".to_string());
+    }
+    if let Some(card) = outcome.noxious_discard {
+        parts.push(format!("lost a {} to a Noxious monster", card));
+    }
+    if outcome.transformed {
+        parts.push("you became Monstrous".to_string());
+    }
+    if outcome.turn_ended {
+        parts.push("new turn".to_string());
+    }
+
+    if parts.is_empty() {
+        return "Card played".to_string();
+    }
+
+    parts.join(", ")
+}
+
+/// Every useful play along with what it would do
+fn hints(game: &GameState) -> String {
+    let mut lines = Vec::new();
+    for legal in legal_moves(game) {
+        let result = match game.preview(legal.command()) {
+            Ok(outcome) => describe(game, &outcome),
+            Err(e) => e.to_string(),
+        };
+
+        lines.push(format!("  {}: {:?} -> {}", legal.hand_index + 1, legal.action, result));
+    }
+
+    if lines.is_empty() {
+        return "  Nothing useful to play, end the turn".to_string();
+    }
+
+    lines.join("\n")
+}
+
+fn main() {
+    let mut seed = None;
+    let mut difficulty = Difficulty::Normal;
+    let mut dungeon_size = None;
+    let mut strict = false;
+    let mut ironman = false;
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--difficulty" => match args.next().as_deref().and_then(Difficulty::by_name) {
+                Some(value) => difficulty = value,
+                None => {
+                    eprintln!("Unknown difficulty, expected one of {:?}", DIFFICULTIES);
+                    std::process::exit(1);
+                }
+            }
+            "--dungeon" => match args.next().as_deref().map(str::parse::<usize>) {
+                Some(Ok(value)) => dungeon_size = Some(value),
+                _ => {
+                    eprintln!("Invalid dungeon size, expected a number of slots");
+                    std::process::exit(1);
+                }
+            }
+            "--strict" => strict = true,
+            "--ironman" => ironman = true,
+            _ => match arg.parse::<u64>() {
+                Ok(value) => seed = Some(value),
+                Err(_) => {
+                    eprintln!("Usage: tui [SEED] [--difficulty NAME] [--dungeon SIZE] [--strict] \
+                               [--ironman]");
+                    std::process::exit(1);
+                }
+            }
+        }
+    }
+
+    // Play with the same monsters and deck as the windowed game
+    let rules = Rules::load(ASSET_DIR)
+        .and_then(|rules| rules.with_difficulty(difficulty))
+        .and_then(|rules| match dungeon_size {
+            Some(size) => rules.with_dungeon_size(size),
+            None => Ok(rules)
+        });
+    let rules = match rules {
+        Ok(rules) => Rules { strict, ..rules },
+        Err(e) => {
+            eprintln!("Failed to load rules: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let seed = seed.unwrap_or_else(rand::random);
+    let mut game = match GameState::with_rules(seed, &rules) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Cannot start game: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let mut replay = Replay::new(seed);
+    replay.ironman = ironman;
+    replay.rules = rules;
+
+    // Snapshots of the game and its replay before each card played this turn
+    let mut history: History<(GameState, Replay)> = match ironman {
+        true => History::disabled(),
+        false => History::new(),
+    };
+
+    if let Err(e) = LifetimeStats::accumulate(STATS_FILE, &[GameEvent::started(&game)]) {
+        eprintln!("Failed to update {}: {}", STATS_FILE, e);
+    }
+    println!("Seed: {}. Type help for the list of commands.", seed);
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut redraw = true;
+    while !game.is_over() {
+        // Only draw the board again once it changed
+        if redraw {
+            println!("\n{}", render(&game));
+        }
+        redraw = false;
+
+        print!("> ");
+        let _ = io::stdout().flush();
+
+        let line = match lines.next() {
+            Some(Ok(line)) => line.to_lowercase(),
+            _ => return,
+        };
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        let command = match words.as_slice() {
+            [] => continue,
+            ["q"] | ["quit"] => return,
+            ["h"] | ["help"] => {
+                println!("{}", HELP);
+                continue;
+            }
+            ["hint"] => {
+                println!("{}", hints(&game));
+                continue;
+            }
+            ["stats"] => {
+                match LifetimeStats::load(STATS_FILE) {
+                    Ok(stats) => print!("{}", stats.to_text(MAX_SURVIVORS_SHOWN)),
+                    Err(e) => println!("Failed to load {}: {}", STATS_FILE, e),
+                }
+                continue;
+            }
+            ["undo"] | ["redo"] => {
+                let mut current = (game.clone(), replay.clone());
+                let done = !ironman && match words[0] {
+                    "undo" => history.undo(&mut current),
+                    _ => history.redo(&mut current),
+                };
+
+                if done {
+                    let (previous_game, previous_replay) = current;
+                    game = previous_game;
+                    replay = previous_replay;
+                    redraw = true;
+                } else {
+                    println!("Nothing to {}", words[0]);
+                }
+                continue;
+            }
+            _ => match parse_command(&line) {
+                Some(command) => command,
+                None => {
+                    println!("Unknown command, type help for the list of commands");
+                    continue;
+                }
+            }
+        };
+
+        let turn = game.turn;
+        let snapshot = (game.clone(), replay.clone());
+        match game.apply(command) {
+            Ok(outcome) => {
+                println!("{}", describe(&game, &outcome));
+                replay.record(turn, command, &outcome);
+                redraw = true;
+
+                history.record(snapshot, &outcome);
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+
+    println!("\n{}\n", render(&game));
+    println!("Game over! {}", if game.is_won() { "YOU WON!" } else { "YOU LOST!" });
+    println!("Payments:   {} ({} * 3)", game.payments * 3, game.payments);
+    println!("Trophies:   {} ({} * 2)", game.trophies * 2, game.trophies);
+    println!("Cards left: {} (hand: {} deck: {})", game.hand.len() + game.deck.len(),
+             game.hand.len(), game.deck.len());
+    println!("Total:      {}", game.score());
+
+    match replay.save_finished(REPLAY_DIR, &game) {
+        Ok(path) => println!("Replay saved to {}", path.display()),
+        Err(e) => eprintln!("Failed to save replay to {}: {}", REPLAY_DIR, e),
+    }
+    if let Err(e) = ScoreTable::record(SCORES_FILE, &game, ironman) {
+        eprintln!("Failed to save score to {}: {}", SCORES_FILE, e);
+    }
+
+    // Every play is counted once the game is over, so plays that were undone are left out
+    if let Err(e) = LifetimeStats::accumulate_game(STATS_FILE, &replay) {
+        eprintln!("Failed to update {}: {}", STATS_FILE, e);
+    }
+}
@@ -1,4 +1,4 @@
-/// Number of monsters that are in the dungeon this game
+/// Default number of slots in the dungeon row, including the deck in the first slot
 pub const MONSTER_DECK_SIZE: usize = 14;
 
 /// Number of padding pixels to add between cards and around the border
@@ -6,3 +6,42 @@ pub const PADDING: f32 = 10.0;
 
 /// Number of random cards to remove from the deck at the beginning of the game
 pub const PAYMENTS: u32 = 5;
+
+/// Number of cards in the starting hand, which is also the hand limit of a regular player
+pub const HAND_SIZE: u8 = 5;
+
+/// Directory that replays of finished games are written to
+pub const REPLAY_DIR: &str = "replays";
+
+/// File the in-progress game is saved to so it can be continued on the next launch
+pub const SAVE_FILE: &str = "savegame.json";
+
+/// Number of random games the Monte Carlo strategy plays out for each command it considers
+pub const MONTE_CARLO_ROLLOUTS: usize = 16;
+
+/// Number of commands a bot plays per second in the window
+pub const BOT_STEPS_PER_SECOND: f32 = 2.0;
+
+/// Directory the images, fonts and monster definitions are loaded from
+pub const ASSET_DIR: &str = "static";
+
+/// File in `ASSET_DIR` defining the monsters that can appear in the dungeon
+pub const MONSTERS_FILE: &str = "monsters.json";
+
+/// File in `ASSET_DIR` defining the cards in the action deck
+pub const DECK_FILE: &str = "deck.json";
+
+/// Most recent cards of the discard pile listed in the discard viewer
+pub const MAX_DISCARDS_SHOWN: usize = 20;
+
+/// File the score of every finished game is appended to
+pub const SCORES_FILE: &str = "scores.jsonl";
+
+/// Number of runs listed on the high-score screen
+pub const MAX_HIGH_SCORES_SHOWN: usize = 10;
+
+/// File the lifetime statistics over every game played are accumulated in
+pub const STATS_FILE: &str = "stats.json";
+
+/// Number of monsters listed among the most frequent survivors on the statistics screen
+pub const MAX_SURVIVORS_SHOWN: usize = 5;
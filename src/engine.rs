@@ -0,0 +1,1494 @@
+//! Headless rules engine for Maverick.
+//!
+//! Everything needed to play a game lives in `GameState` and is driven by `GameState::apply`.
+//! Nothing in here knows about windows, images or mouse clicks, so games can be played, tested
+//! and simulated without a display.
+
+use std::fmt;
+
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+
+use crate::rng::GameRng;
+use crate::rules::{ConfigError, Difficulty, MonsterDef, Rules, MIN_DUNGEON_SIZE};
+
+/// Which entity an action can be performed on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Entity {
+    Character,
+    Companion
+}
+
+/// Direction which an ability is performed in the dungeon row
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    Left,
+    Right
+}
+
+/// Available actions the player can perform in the game
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Action {
+    Range(Entity, Direction),
+    Melee(Entity),
+    Move(Entity, Direction),
+    Swap,
+    EndTurn,
+
+    /// Lose the card to a Noxious monster, when the player chooses the Noxious discards
+    Discard
+}
+
+impl Action {
+    /// Name of the kind of action, whoever performs it and in whichever direction
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Range(..) => "Range",
+            Action::Melee(_) => "Melee",
+            Action::Move(..) => "Move",
+            Action::Swap => "Swap",
+            Action::EndTurn => "EndTurn",
+            Action::Discard => "Discard",
+        }
+    }
+}
+
+/// Special abilities that some monsters have
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ability {
+    Noxious,
+    Rally,
+    Reign
+}
+
+impl Ability {
+    /// Plain description of what the ability does during a game
+    pub fn description(&self) -> &'static str {
+        match self {
+            Ability::Noxious => "Noxious: moving onto it loses a card from the hand",
+            Ability::Rally => "Rally: its neighbours are 1 stronger while it is alive",
+            Ability::Reign => "Reign: cannot be slain while a neighbour at least as strong is alive",
+        }
+    }
+}
+
+/// How the card lost to a Noxious monster is picked
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum NoxiousDiscard {
+    /// A random card from the hand is lost
+    Random,
+
+    /// The player chooses the card to lose with `Action::Discard`
+    Chosen
+}
+
+/// Actions needed to be performed on a monster in order to kill it
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToSlay {
+    Melee,
+    Range,
+    Move
+}
+
+impl ToSlay {
+    /// Single letter used for this hit in text renderings of the board
+    pub fn symbol(&self) -> char {
+        match self {
+            ToSlay::Move => 'M',
+            ToSlay::Range => 'R',
+            ToSlay::Melee => 'X',
+        }
+    }
+}
+
+/// Slot of the dungeon row holding the deck. It is never alive, and every other slot holds a
+/// monster.
+pub const DECK_SLOT: usize = 0;
+
+/// Monster stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Monsters {
+    pub names: Vec<String>,
+    pub strengths: Vec<u8>,
+    pub strength_adjustments: Vec<u8>,
+    pub abilities: Vec<Option<Ability>>,
+    pub to_slays: Vec<Vec<ToSlay>>,
+    pub current_hits: Vec<Vec<ToSlay>>,
+    pub alive: Vec<bool>,
+
+    /// Path of each monster's image, relative to the asset directory
+    pub images: Vec<String>
+}
+
+impl Monsters {
+    /// Return the current strength of the given index by adding the adjustment to the base strength
+    pub fn strength(&self, index: usize) -> Result<u8, RuleError> {
+        if index >= self.len() {
+            return Err(RuleError::TargetOffBoard { index, dungeon_size: self.len() });
+        }
+
+        Ok(self.strengths[index] + self.strength_adjustments[index])
+    }
+}
+
+impl Monsters {
+    /// Number of slots in the dungeon row, including the deck in the first slot
+    pub fn len(&self) -> usize {
+        self.alive.len()
+    }
+
+    /// Returns `true` if the dungeon row has no slots at all
+    pub fn is_empty(&self) -> bool {
+        self.alive.is_empty()
+    }
+
+    /// Indexes of every slot holding a monster, dead or alive
+    pub fn monster_slots(&self) -> std::ops::Range<usize> {
+        DECK_SLOT + 1..self.len()
+    }
+
+    /// Initialize a dungeon row of `dungeon_size` slots for this game, drawing monsters from
+    /// `defs` using the given `rng`
+    pub fn init<R: Rng>(rng: &mut R, defs: &[MonsterDef], dungeon_size: usize)
+            -> Result<Monsters, RuleError> {
+        // Every slot but the deck needs a different monster, or the draw below never finishes
+        if dungeon_size < MIN_DUNGEON_SIZE || dungeon_size > defs.len() + 1 {
+            return Err(RuleError::InvalidConfiguration(ConfigError::DungeonSize {
+                size: dungeon_size,
+                min: MIN_DUNGEON_SIZE,
+                max: defs.len() + 1
+            }));
+        }
+
+        // Create the monster deck via a random selection of a different monster for every slot
+        // but `DECK_SLOT`
+        let mut monster_indexes = Vec::new();
+        loop {
+            if monster_indexes.len() == dungeon_size - 1 {
+                break;
+            }
+
+            let mut index = rng.gen_range(0, defs.len());
+            loop {
+                if !monster_indexes.contains(&index) {
+                    monster_indexes.push(index);
+                    break;
+                }
+
+                index = rng.gen_range(0, defs.len());
+            }
+        }
+
+        // Init the monsters struct
+        let mut monsters = Monsters {
+            names: Vec::new(),
+            strengths: Vec::new(),
+            strength_adjustments: Vec::new(),
+            abilities: Vec::new(),
+            to_slays: Vec::new(),
+            current_hits: Vec::new(),
+            alive: Vec::new(),
+            images: Vec::new(),
+        };
+
+        // The deck comes first, in `DECK_SLOT`
+        monsters.names.push("Deck".to_string());
+        monsters.strengths.push(0);
+        monsters.strength_adjustments.push(0);
+        monsters.abilities.push(None);
+        monsters.to_slays.push(Vec::new());
+        monsters.current_hits.push(Vec::new());
+        monsters.alive.push(false);
+        monsters.images.push("action.png".to_string());
+
+
+        // Populate the Monsters struct
+        for &index in &monster_indexes {
+            // Get the definition of the current monster
+            let def = &defs[index];
+
+            // Populate these monster fields
+            monsters.names.push(def.name.clone());
+            monsters.strengths.push(def.strength);
+            monsters.strength_adjustments.push(0);
+            monsters.abilities.push(def.ability);
+            monsters.alive.push(true);
+            monsters.to_slays.push(def.to_slay.clone());
+            monsters.images.push(def.image.clone());
+
+            // Init the current hits for each monster
+            monsters.current_hits.push(Vec::new());
+        }
+
+        monsters.rally();
+
+        Ok(monsters)
+    }
+
+    /// Returns `true` if the monster at `index` is protected by Reign: it has the Reign ability
+    /// and an alive neighbour with at least its strength
+    pub fn reign(&self, index: usize) -> bool {
+        !self.reign_blockers(index).is_empty()
+    }
+
+    /// Alive neighbours protecting the monster at `index` with Reign, which have to be slain
+    /// before it can be
+    pub fn reign_blockers(&self, index: usize) -> Vec<usize> {
+        let mut blockers = Vec::new();
+
+        if index >= self.len() || !matches!(self.abilities[index], Some(Ability::Reign)) {
+            return blockers;
+        }
+
+        // Every index below is in bounds
+        let strength = |index: usize| self.strengths[index] + self.strength_adjustments[index];
+        let curr_strength = strength(index);
+
+        if index > 0 && self.alive[index - 1] && strength(index - 1) >= curr_strength {
+            blockers.push(index - 1);
+        }
+
+        if index < (self.len() - 1) && self.alive[index + 1]
+                && strength(index + 1) >= curr_strength {
+            blockers.push(index + 1);
+        }
+
+        blockers
+    }
+
+    /// Hits the monster at `index` still needs this turn to be slain: its `to_slays` without
+    /// its `current_hits`
+    pub fn remaining_hits(&self, index: usize) -> Vec<ToSlay> {
+        let mut to_slays = self.to_slays[index].clone();
+
+        // Remove all current hits from the to_slays vec
+        for curr_hit in &self.current_hits[index] {
+            if let Some(pos) = to_slays.iter().position(|x| x == curr_hit) {
+                to_slays.remove(pos);
+            }
+        }
+
+        to_slays
+    }
+
+    /// Recalculate the strength adjustments from every alive Rally monster
+    pub fn rally(&mut self) {
+        // Reset all strength adjustments
+        for adjustment in self.strength_adjustments.iter_mut() {
+            *adjustment = 0;
+        }
+
+        // Adjust the strength_adjustments for Rally monsters if that monster is alive
+        for index in 0..self.len() {
+            if matches!(self.abilities[index], Some(Ability::Rally)) && self.alive[index] {
+                if index > 0 {
+                    self.strength_adjustments[index - 1] += 1;
+                }
+
+                if index < (self.len() - 1) {
+                    self.strength_adjustments[index + 1] += 1;
+                }
+            }
+        }
+    }
+}
+
+/// Current player kind. Player starts as `Regular` and shifts to `Monstrous` if 5 actions are
+/// spent on any one turn
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PlayerKind {
+    Regular,
+    Monstrous
+}
+
+/// Types of companions available
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CompanionKind {
+    Melee,
+    Range
+}
+
+/// A single request made to the rules engine
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Command {
+    /// Play the card at the given hand index for the given action
+    Play(Action, usize),
+
+    /// End the current turn, replenishing the hand
+    EndTurn
+}
+
+/// A card that left the hand, and why
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DiscardedCard {
+    /// Value of the card
+    pub card: u8,
+
+    /// Turn the card left the hand
+    pub turn: u32,
+
+    /// Action the card was played for, or `Action::Discard` if it was lost to a Noxious monster
+    pub action: Action,
+
+    /// Slot reached by the action, or of the Noxious monster the card was lost to
+    pub target: Option<usize>,
+}
+
+impl DiscardedCard {
+    /// Returns `true` if the card was lost to a Noxious monster rather than played
+    pub fn is_noxious_loss(&self) -> bool {
+        self.action == Action::Discard
+    }
+}
+
+/// Result of successfully applying a `Command`
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    /// Value of the card played from the hand
+    pub card: Option<u8>,
+
+    /// Monster index targeted by the action, if the action reached the monster row
+    pub target: Option<usize>,
+
+    /// Hit token added to the target
+    pub hit: Option<ToSlay>,
+
+    /// A trophy was gained by this action
+    pub trophy: bool,
+
+    /// The target was slain by this action
+    pub killed: bool,
+
+    /// Card lost to a Noxious monster
+    pub noxious_discard: Option<u8>,
+
+    /// A card must be chosen to lose to a Noxious monster before anything else can be done
+    pub discard_pending: bool,
+
+    /// The player transformed into the Monstrous form
+    pub transformed: bool,
+
+    /// The turn ended and the hand was replenished
+    pub turn_ended: bool,
+
+    /// The game is over after this command
+    pub game_over: bool,
+}
+
+/// Reasons a `Command` can be rejected by the rules engine
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RuleError {
+    /// The hand index does not refer to a card in the hand
+    CardOutOfRange { index: usize, hand_size: usize },
+
+    /// The game has already ended
+    GameOver,
+
+    /// A card must be discarded to a Noxious monster first
+    DiscardPending,
+
+    /// There is no Noxious discard to choose
+    NothingToDiscard,
+
+    /// The slot at `index` is not in the dungeon row
+    TargetOffBoard { index: usize, dungeon_size: usize },
+
+    /// The monster at `index` has already been slain
+    MonsterDead { index: usize },
+
+    /// The rules cannot create a game
+    InvalidConfiguration(ConfigError),
+
+    /// Strict mode: the attack would miss every monster
+    OutOfReach,
+
+    /// Strict mode: the card is weaker than the monster at `index`
+    TooWeak { index: usize, card: u8, strength: u8 },
+
+    /// Strict mode: the monster at `index` is not slain with the help of `to_slay` hits
+    NotRequired { index: usize, to_slay: ToSlay },
+
+    /// The companion of the given kind cannot perform the action
+    WrongCompanion(CompanionKind),
+
+    /// A card of value 0 cannot move anyone or reach another slot
+    ZeroDistance,
+}
+
+impl fmt::Display for RuleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RuleError::CardOutOfRange { index, hand_size } =>
+                write!(f, "Card {} is not in the hand of {} cards", index, hand_size),
+            RuleError::GameOver => write!(f, "The game is over"),
+            RuleError::DiscardPending => write!(f, "A card must be discarded to the Noxious monster"),
+            RuleError::NothingToDiscard => write!(f, "There is no card to discard"),
+            RuleError::TargetOffBoard { index, dungeon_size } =>
+                write!(f, "Slot {} is outside the dungeon of {} slots", index, dungeon_size),
+            RuleError::MonsterDead { index } => write!(f, "The monster in slot {} is already dead", index),
+            RuleError::InvalidConfiguration(e) => write!(f, "Invalid rules: {}", e),
+            RuleError::OutOfReach => write!(f, "The attack would miss every monster"),
+            RuleError::TooWeak { index, card, strength } =>
+                write!(f, "A {} is too weak for the monster in slot {} of strength {}",
+                       card, index, strength),
+            RuleError::NotRequired { index, to_slay } =>
+                write!(f, "The monster in slot {} does not need a {:?} hit", index, to_slay),
+            RuleError::WrongCompanion(kind) => write!(f, "The {:?} companion cannot do that", kind),
+            RuleError::ZeroDistance => write!(f, "A 0 can only be used to Melee or Swap"),
+        }
+    }
+}
+
+impl std::error::Error for RuleError {}
+
+/// Complete rules state of a single game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameState {
+    /// Seed used to create this game
+    pub seed: u64,
+
+    /// Source of every random decision made in this game, seeded from `seed`
+    rng: GameRng,
+
+    /// Monsters in this game
+    pub monsters: Monsters,
+
+    /// Current index of the player
+    pub player_index: usize,
+
+    /// Type of player currently active
+    pub player_kind: PlayerKind,
+
+    /// Current index of the companion
+    pub companion_index: usize,
+
+    /// Type of companion currently active
+    pub companion_kind: CompanionKind,
+
+    /// Deck containing the action cards
+    pub deck: Vec<u8>,
+
+    /// Current hand. The engine keeps it sorted after every deal and draw, so hand indices
+    /// follow the order the cards are shown in.
+    pub hand: Vec<u8>,
+
+    /// Maximum hand size: the starting hand size for a regular player and one more when player
+    /// becomes Monstrous by playing a whole hand in one turn
+    pub hand_limit: u8,
+
+    /// Has a card been discarded in a turn. Used when checking if to transform to Monstrous form
+    pub discarded: bool,
+
+    /// How cards lost to Noxious monsters are picked
+    pub noxious: NoxiousDiscard,
+
+    /// The player has moved onto a Noxious monster and must choose a card to lose
+    pub discard_pending: bool,
+
+    /// Number of cards withheld at the beginning of the game. Worth 3 points each if game is won.
+    pub payments: u32,
+
+    /// Cards withheld as payments, never drawn and hidden from the player
+    pub withheld: Vec<u8>,
+
+    /// Every card that left the hand since the deck was last reshuffled, oldest first
+    pub discard_pile: Vec<DiscardedCard>,
+
+    /// Number of times the discard pile can still be shuffled back into an empty deck
+    pub reshuffles: u32,
+
+    /// Trophies gathered during the course of the game
+    pub trophies: u32,
+
+    /// Current turn number, starting at 1
+    pub turn: u32,
+
+    /// Difficulty of the rules this game was created with
+    pub difficulty: Difficulty,
+
+    /// Plays that would waste the card are rejected instead, keeping the card in the hand
+    pub strict: bool,
+}
+
+impl GameState {
+    /// Create a new game from the given `seed` using the default rules. The same seed always
+    /// produces the same dungeon, companion, deck order and Noxious discards.
+    pub fn new(seed: u64) -> GameState {
+        GameState::with_rules(seed, &Rules::default()).expect("Default rules are invalid")
+    }
+
+    /// Create a new game from the given `seed` using the given `rules`, checking that the
+    /// rules fit together
+    pub fn with_rules(seed: u64, rules: &Rules) -> Result<GameState, RuleError> {
+        rules.validate().map_err(RuleError::InvalidConfiguration)?;
+
+        let mut rng = GameRng::new(seed);
+
+        // Create the monster deck for this game
+        let monsters = Monsters::init(&mut rng, &rules.monsters, rules.dungeon_size)?;
+
+        let companion_kind = match rng.gen::<u8>() & 1 {
+            0 => CompanionKind::Melee,
+            1 => CompanionKind::Range,
+            _ => unreachable!()
+        };
+
+        // Generate the deck itself
+        let mut deck = rules.deck_cards();
+
+        // Number of initial cards removed
+        let payments = rules.payments;
+
+        // Shuffle the deck
+        deck.shuffle(&mut rng);
+
+        // Withhold cards equal to payment from the top of the deck
+        let withheld = deck.split_off(deck.len() - payments as usize);
+
+        // Populate the initial hand
+        let mut hand = Vec::new();
+        for _ in 0..rules.hand_size {
+            hand.push(deck.pop().unwrap());
+        }
+        hand.sort();
+
+        Ok(GameState {
+            seed,
+            rng,
+            monsters,
+            player_index: 0,
+            player_kind: PlayerKind::Regular,
+            companion_index: 0,
+            companion_kind,
+            deck,
+            hand,
+            hand_limit: rules.hand_size,
+            discarded: false,
+            noxious: rules.noxious,
+            discard_pending: false,
+            payments,
+            withheld,
+            discard_pile: Vec::new(),
+            reshuffles: rules.reshuffles,
+            trophies: 0,
+            turn: 1,
+            difficulty: rules.difficulty,
+            strict: rules.strict,
+        })
+    }
+
+    /// Position of the game's random number generator, identifying the random decisions still
+    /// to come
+    pub fn rng_position(&self) -> u128 {
+        self.rng.position()
+    }
+
+    /// Number of slots in the dungeon row, including the deck in the first slot
+    pub fn dungeon_size(&self) -> usize {
+        self.monsters.len()
+    }
+
+    /// Current index of the given entity in the dungeon row
+    pub fn position(&self, entity: Entity) -> usize {
+        match entity {
+            Entity::Character => self.player_index,
+            Entity::Companion => self.companion_index,
+        }
+    }
+
+    /// Returns `true` once every monster is dead or the player has run out of cards
+    pub fn is_over(&self) -> bool {
+        // If all monsters are dead, game is over
+        if self.is_won() {
+            return true;
+        }
+
+        // End game is triggered when no cards in hand and no cards left to draw
+        self.hand.is_empty() && self.deck.is_empty() && !self.can_reshuffle()
+    }
+
+    /// Returns `true` if an empty deck would be refilled by shuffling the discard pile back in
+    pub fn can_reshuffle(&self) -> bool {
+        self.reshuffles > 0 && !self.discard_pile.is_empty()
+    }
+
+    /// Returns `true` if every monster in the dungeon has been slain
+    pub fn is_won(&self) -> bool {
+        self.monsters.alive.iter().all(|&x| !x)
+    }
+
+    /// Current score: 3 per payment, 2 per trophy and 1 per card left in the hand and deck
+    pub fn score(&self) -> u32 {
+        self.payments * 3 + self.trophies * 2 + self.hand.len() as u32 + self.deck.len() as u32
+    }
+
+    /// Apply the given `Command` to the game, returning what happened as a result
+    pub fn apply(&mut self, command: Command) -> Result<Outcome, RuleError> {
+        if self.is_over() {
+            return Err(RuleError::GameOver);
+        }
+
+        let mut outcome = Outcome::default();
+
+        // Variables set if an action is valid
+        let mut reset = false;
+
+        // A pending Noxious discard must be resolved before anything else
+        match command {
+            Command::Play(Action::Discard, _) if !self.discard_pending =>
+                return Err(RuleError::NothingToDiscard),
+            Command::Play(Action::Discard, _) => {}
+            _ if self.discard_pending => return Err(RuleError::DiscardPending),
+            _ => {}
+        }
+
+        match command {
+            Command::EndTurn | Command::Play(Action::EndTurn, _) => reset = true,
+            Command::Play(action, hand_index) => {
+                // Ensure our hand_index is in bounds
+                let num = match self.hand.get(hand_index) {
+                    Some(&num) => num,
+                    None => return Err(RuleError::CardOutOfRange {
+                        index: hand_index,
+                        hand_size: self.hand.len()
+                    })
+                };
+
+                // Reject attacks on monsters that are already dead, or by the wrong companion,
+                // before the card is spent
+                self.check_target(action, num)?;
+                if self.strict {
+                    self.check_effect(action, num)?;
+                }
+
+                // Remove the card from the hand
+                self.hand.remove(hand_index);
+                outcome.card = Some(num);
+
+                match action {
+                    Action::Move(entity, direction) => self.play_move(entity, direction, num, &mut outcome)?,
+                    Action::Range(entity, direction) => self.play_range(entity, direction, num, &mut outcome)?,
+                    Action::Melee(entity) => self.play_melee(entity, num, &mut outcome)?,
+                    Action::Swap => {
+                        // Change the companion to the other kind
+                        self.companion_kind = match self.companion_kind {
+                            CompanionKind::Melee => CompanionKind::Range,
+                            CompanionKind::Range => CompanionKind::Melee,
+                        };
+                    }
+                    Action::Discard => {
+                        // The card is lost rather than played
+                        outcome.card = None;
+                        outcome.noxious_discard = Some(num);
+                        self.discarded = true;
+                        self.discard_pending = false;
+                    }
+                    Action::EndTurn => unreachable!("Ending the turn is not a card play\n{}", self)
+                }
+
+                // Record why the cards left the hand. A card lost to a Noxious monster always
+                // follows the move onto that monster.
+                if action != Action::Discard {
+                    self.discard_pile.push(DiscardedCard {
+                        card: num,
+                        turn: self.turn,
+                        action,
+                        target: outcome.target
+                    });
+                }
+
+                if let Some(lost) = outcome.noxious_discard {
+                    let target = self.discard_pile.last().and_then(|discarded| discarded.target);
+                    self.discard_pile.push(DiscardedCard {
+                        card: lost,
+                        turn: self.turn,
+                        action: Action::Discard,
+                        target
+                    });
+                }
+            }
+        }
+
+        // Check if the current monster is dead by removing all elements from the
+        // current hit Vector from the to_slay Vector. If at the end of that, the
+        // to_slays vector is empty, then that monster is dead.
+        if let Some(index) = outcome.target {
+            if self.monsters.alive[index] {
+                // If no hits remain, we have enough hits for the monster to be dead
+                if self.monsters.remaining_hits(index).is_empty() && !self.monsters.reign(index) {
+                    info!("Slain {}", self.monsters.names[index]);
+                    self.monsters.alive[index] = false;
+                    self.monsters.current_hits[index].clear();
+                    outcome.killed = true;
+                }
+            }
+        }
+
+        // Recalculate the Rally adjustments now that monsters may have died
+        self.monsters.rally();
+
+        // We are out of cards in hand and should reset
+        if self.hand.is_empty() && !self.discarded {
+            reset = true;
+
+            // If we ran out of cards then we can always say the player is Monstrous
+            if self.player_kind == PlayerKind::Regular {
+                outcome.transformed = true;
+                self.hand_limit += 1;
+            }
+            self.player_kind = PlayerKind::Monstrous;
+        }
+
+        if reset {
+            // Replinish cards up to hand limit
+            for _ in 0..(self.hand_limit - self.hand.len() as u8) {
+                if self.deck.is_empty() {
+                    self.reshuffle();
+                }
+
+                if let Some(new_card) = self.deck.pop() {
+                    self.hand.push(new_card);
+                }
+            }
+            self.hand.sort();
+
+            // Reset the current hits on all monsters
+            for curr_hit in self.monsters.current_hits.iter_mut() {
+                curr_hit.clear();
+            }
+
+            self.turn += 1;
+            outcome.turn_ended = true;
+        }
+
+        self.discarded = false;
+        outcome.discard_pending = self.discard_pending;
+        outcome.game_over = self.is_over();
+
+        Ok(outcome)
+    }
+
+    /// Shuffle the discard pile into a new deck, if the rules allow another reshuffle
+    fn reshuffle(&mut self) {
+        if !self.can_reshuffle() {
+            return;
+        }
+
+        info!("Reshuffling {} discarded cards", self.discard_pile.len());
+        self.deck = self.discard_pile.drain(..).map(|discarded| discarded.card).collect();
+        self.deck.shuffle(&mut self.rng);
+        self.reshuffles -= 1;
+    }
+
+    /// Returns an error if playing `num` for `action` would attack a slot outside the dungeon
+    /// or a monster that is already dead, needs a companion of the other kind, or moves or
+    /// shoots a distance of 0
+    fn check_target(&self, action: Action, num: u8) -> Result<(), RuleError> {
+        if num == 0 && matches!(action, Action::Move(..) | Action::Range(..)) {
+            return Err(RuleError::ZeroDistance);
+        }
+
+        // Only a Range companion can shoot and only a Melee companion can fight
+        let kind = match action {
+            Action::Range(Entity::Companion, _) => Some(CompanionKind::Range),
+            Action::Melee(Entity::Companion) => Some(CompanionKind::Melee),
+            _ => None
+        };
+
+        if matches!(kind, Some(kind) if kind != self.companion_kind) {
+            return Err(RuleError::WrongCompanion(self.companion_kind));
+        }
+
+        let index = match action {
+            Action::Range(entity, direction) => match self.range_target(entity, direction, num) {
+                Some(index) => index,
+                None => return Ok(())
+            }
+            Action::Melee(entity) => self.position(entity),
+            _ => return Ok(())
+        };
+
+        match self.monsters.alive.get(index) {
+            None => Err(RuleError::TargetOffBoard { index, dungeon_size: self.dungeon_size() }),
+            Some(false) if index != DECK_SLOT => Err(RuleError::MonsterDead { index }),
+            _ => Ok(())
+        }
+    }
+
+    /// Returns an error explaining why playing `num` for `action` would spend the card without
+    /// hitting anything. Moves and swaps always have an effect.
+    fn check_effect(&self, action: Action, num: u8) -> Result<(), RuleError> {
+        let (entity, to_slay) = match action {
+            Action::Range(entity, _) => (entity, ToSlay::Range),
+            Action::Melee(entity) => (entity, ToSlay::Melee),
+            _ => return Ok(())
+        };
+
+        let index = match action {
+            Action::Range(entity, direction) => self.range_target(entity, direction, num),
+            _ => Some(self.position(entity)),
+        };
+
+        let index = match index {
+            Some(index) if index != DECK_SLOT => index,
+            _ => return Err(RuleError::OutOfReach)
+        };
+
+        let strength = self.monsters.strength(index)?;
+        if to_slay == ToSlay::Melee && num < strength {
+            return Err(RuleError::TooWeak { index, card: num, strength });
+        }
+
+        if !self.monsters.to_slays[index].contains(&to_slay) {
+            return Err(RuleError::NotRequired { index, to_slay });
+        }
+
+        Ok(())
+    }
+
+    /// Result of applying `command` without changing the game. Plays that would waste the card
+    /// are rejected with an explanation, as in strict mode.
+    pub fn preview(&self, command: Command) -> Result<Outcome, RuleError> {
+        let mut game = self.clone();
+        game.strict = true;
+        game.apply(command)
+    }
+
+    /// Add a `ToSlay` hit to the monster at `index` if that monster requires it, gaining a
+    /// trophy if the card played exactly matches the monster's strength
+    fn hit(&mut self, index: usize, to_slay: ToSlay, num: u8, outcome: &mut Outcome)
+            -> Result<(), RuleError> {
+        let strength = self.monsters.strength(index)?;
+        outcome.target = Some(index);
+
+        // Only add hits if the monster actually can be hit by this action
+        if !self.monsters.to_slays[index].contains(&to_slay) {
+            return Ok(());
+        }
+
+        self.monsters.current_hits[index].push(to_slay);
+        outcome.hit = Some(to_slay);
+
+        // Check if we should get a trophy
+        if num == strength {
+            self.trophies += 1;
+            outcome.trophy = true;
+        }
+
+        Ok(())
+    }
+
+    /// Move the given entity `num` spaces in `direction`, stopping at the edges of the dungeon
+    fn play_move(&mut self, entity: Entity, direction: Direction, num: u8,
+            outcome: &mut Outcome) -> Result<(), RuleError> {
+        let curr_index = self.position(entity);
+
+        let index = match direction {
+            Direction::Left => {
+                // Ensure we never move back onto the dungeon deck when moving left
+                std::cmp::max(curr_index.saturating_sub(num as usize), 1)
+            }
+            Direction::Right => {
+                std::cmp::min(curr_index + num as usize, self.dungeon_size() - 1)
+            }
+        };
+
+        match entity {
+            Entity::Character => self.player_index = index,
+            Entity::Companion => self.companion_index = index,
+        }
+
+        info!("New {:?} index {:?}: {}", entity, direction, index);
+
+        self.hit(index, ToSlay::Move, num, outcome)?;
+
+        // Moving onto a Noxious monster results in losing a card, either at random or chosen
+        // by the player with the next command
+        if matches!(self.monsters.abilities[index], Some(Ability::Noxious))
+                && self.monsters.alive[index] && !self.hand.is_empty() {
+            match self.noxious {
+                NoxiousDiscard::Random => {
+                    let lost = self.hand.remove(self.rng.gen_range(0, self.hand.len()));
+                    outcome.noxious_discard = Some(lost);
+                    self.discarded = true;
+                }
+                NoxiousDiscard::Chosen => self.discard_pending = true,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Slot hit by a Range attack from the given entity `num` spaces in `direction`, or `None`
+    /// if the attack would leave the dungeon
+    fn range_target(&self, entity: Entity, direction: Direction, num: u8) -> Option<usize> {
+        let curr_index = self.position(entity);
+
+        match direction {
+            Direction::Left => curr_index.checked_sub(num as usize),
+            Direction::Right => {
+                if curr_index + (num as usize) >= self.dungeon_size() {
+                    return None;
+                }
+                Some(curr_index + num as usize)
+            }
+        }
+    }
+
+    /// Fire a Range attack from the given entity `num` spaces in `direction`. Attacks that
+    /// would leave the dungeon have no effect.
+    fn play_range(&mut self, entity: Entity, direction: Direction, num: u8,
+            outcome: &mut Outcome) -> Result<(), RuleError> {
+        // Ensure we are in bounds for the range attack
+        let index = match self.range_target(entity, direction, num) {
+            Some(index) => index,
+            None => return Ok(())
+        };
+
+        info!("Range {:?} {:?} {} hitting {}", direction, entity, num, self.monsters.names[index]);
+
+        self.hit(index, ToSlay::Range, num, outcome)
+    }
+
+    /// Melee the monster under the given entity. The attack only lands if the card is at least
+    /// the monster's current strength.
+    fn play_melee(&mut self, entity: Entity, num: u8, outcome: &mut Outcome)
+            -> Result<(), RuleError> {
+        // Get the monster index based on the entity using Melee
+        let index = self.position(entity);
+
+        // If the action card number is greater than or equal to the monster strength,
+        // it is a successful melee attack
+        if num >= self.monsters.strength(index)? {
+            self.hit(index, ToSlay::Melee, num, outcome)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for GameState {
+    /// Create a new game from a random seed
+    fn default() -> GameState {
+        GameState::new(rand::random())
+    }
+}
+
+impl fmt::Display for GameState {
+    /// Compact text rendering of the whole board, one line per slot of the dungeon row. Each
+    /// required hit is followed by `+` if it was already dealt this turn and `-` otherwise.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let hand: Vec<String> = self.hand.iter().map(|card| card.to_string()).collect();
+        writeln!(f, "seed {} turn {} trophies {} deck {} withheld {} hand [{}] limit {} {:?}",
+                 self.seed, self.turn, self.trophies, self.deck.len(), self.withheld.len(),
+                 hand.join(" "), self.hand_limit, self.player_kind)?;
+
+        if self.discard_pending {
+            writeln!(f, "discard pending")?;
+        }
+
+        for index in 0..self.dungeon_size() {
+            let monsters = &self.monsters;
+            let mut line = format!("{:>2} {:<12}", index, monsters.names[index]);
+
+            if index != DECK_SLOT {
+                let strength = match monsters.strength_adjustments[index] {
+                    0 => monsters.strengths[index].to_string(),
+                    adjustment => format!("{}+{}", monsters.strengths[index], adjustment),
+                };
+
+                let mut dealt = monsters.current_hits[index].clone();
+                let hits: String = monsters.to_slays[index].iter().map(|to_slay| {
+                    let mark = match dealt.iter().position(|hit| hit == to_slay) {
+                        Some(pos) => {
+                            dealt.remove(pos);
+                            '+'
+                        }
+                        None => '-'
+                    };
+                    format!("{}{}", to_slay.symbol(), mark)
+                }).collect();
+
+                let ability = match monsters.abilities[index] {
+                    Some(ability) => format!("{:?}", ability),
+                    None => String::new(),
+                };
+
+                let state = if monsters.alive[index] { "alive" } else { "dead" };
+                line.push_str(&format!(" {:<5} {:<4} {:<6} {:<7}", state, strength, hits, ability));
+            }
+
+            if index == self.player_index {
+                line.push_str(" @");
+            }
+            if index == self.companion_index {
+                line.push_str(&format!(" {:?}", self.companion_kind));
+            }
+
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Game with a dungeon row of the given monsters, each a strength, an ability and the hits
+    /// needed to slay it, and with the character and a Melee companion on the deck holding
+    /// `hand`
+    pub(crate) fn board(monsters: &[(u8, Option<Ability>, &[ToSlay])], hand: &[u8]) -> GameState {
+        let mut game = GameState::new(0);
+        let mut names = vec!["Deck".to_string()];
+        names.extend((1..=monsters.len()).map(|index| format!("Monster {}", index)));
+
+        let mut strengths = vec![0];
+        let mut abilities = vec![None];
+        let mut to_slays = vec![Vec::new()];
+        for &(strength, ability, to_slay) in monsters {
+            strengths.push(strength);
+            abilities.push(ability);
+            to_slays.push(to_slay.to_vec());
+        }
+
+        let slots = monsters.len() + 1;
+        let mut alive = vec![true; slots];
+        alive[DECK_SLOT] = false;
+
+        game.monsters = Monsters {
+            names,
+            strengths,
+            strength_adjustments: vec![0; slots],
+            abilities,
+            to_slays,
+            current_hits: vec![Vec::new(); slots],
+            alive,
+            images: vec![String::new(); slots],
+        };
+        game.monsters.rally();
+
+        game.companion_kind = CompanionKind::Melee;
+        game.hand = hand.to_vec();
+        game
+    }
+
+    /// Plain monster of the given strength slain by a single hit of `to_slay`
+    pub(crate) fn plain(strength: u8, to_slay: &[ToSlay]) -> (u8, Option<Ability>, &[ToSlay]) {
+        (strength, None, to_slay)
+    }
+
+    fn play(action: Action, hand_index: usize) -> Command {
+        Command::Play(action, hand_index)
+    }
+
+    /// Apply `command`, checking that it is rejected with `error` and leaves the game untouched
+    fn assert_rejected(game: &mut GameState, command: Command, error: RuleError) {
+        let before = serde_json::to_string(&*game).unwrap();
+        assert_eq!(game.apply(command), Err(error), "\n{}", game);
+        assert_eq!(serde_json::to_string(&*game).unwrap(), before, "\n{}", game);
+    }
+
+    const RIGHT: Direction = Direction::Right;
+    const CHARACTER: Entity = Entity::Character;
+
+    /// Board of the game created from seed 42 with the default rules
+    const SEED_42: &str = "\
+seed 42 turn 1 trophies 0 deck 30 withheld 5 hand [1 1 2 4 5] limit 5 Regular
+ 0 Deck         @ Range
+ 1 Scorpion     alive 1    X-R-   Noxious
+ 2 Werewolf     alive 2    X-X-
+ 3 Lich         alive 4    R-     Reign
+ 4 Elemental    alive 2    X-R-
+ 5 Spider       alive 1    R-R-   Noxious
+ 6 Dragon       alive 5+1  M-X-R- Reign
+ 7 Howler       alive 4    X-     Rally
+ 8 Troll        alive 3+1  M-R-
+ 9 Skeleton     alive 2    M-X-
+10 Demon        alive 5    X-R-
+11 Imp          alive 0    M-M-
+12 Beholder     alive 1+1  R-R-R-
+13 Troglodyte   alive 1    M-X-   Rally
+";
+
+    #[test]
+    fn board_of_a_fixed_seed() {
+        let mut game = GameState::new(42);
+        assert_eq!(game.to_string(), SEED_42);
+
+        // Moving onto the Noxious Scorpion loses a random card along with the one played
+        let command = Command::Play(Action::Move(Entity::Character, Direction::Right), 0);
+        let outcome = game.apply(command).unwrap();
+        assert_eq!(outcome.card, Some(1));
+        assert!(outcome.noxious_discard.is_some());
+
+        let board = game.to_string();
+        assert!(board.contains(" 0 Deck         Range\n"), "\n{}", board);
+        assert!(board.contains(" 1 Scorpion     alive 1    X-R-   Noxious @\n"), "\n{}", board);
+        assert_eq!(game.hand.len(), 3, "\n{}", board);
+    }
+
+    #[test]
+    fn wrong_companion_is_rejected() {
+        let plays = [
+            (CompanionKind::Melee, Action::Range(Entity::Companion, Direction::Right)),
+            (CompanionKind::Range, Action::Melee(Entity::Companion)),
+        ];
+
+        for &(kind, action) in plays.iter() {
+            let mut game = GameState::new(0);
+            assert!(!game.strict);
+            game.companion_kind = kind;
+            let hand = game.hand.clone();
+
+            assert_eq!(game.apply(Command::Play(action, 0)), Err(RuleError::WrongCompanion(kind)));
+            assert_eq!(game.hand, hand);
+            assert!(game.discard_pile.is_empty());
+        }
+    }
+
+    #[test]
+    fn wrong_companion_comes_before_strict_errors() {
+        let mut game = GameState::new(0);
+        game.companion_kind = CompanionKind::Melee;
+
+        // Shooting off the board would otherwise be out of reach
+        let command = Command::Play(Action::Range(Entity::Companion, Direction::Left), 0);
+        assert_eq!(game.preview(command), Err(RuleError::WrongCompanion(CompanionKind::Melee)));
+
+        game.strict = true;
+        assert_eq!(game.apply(command), Err(RuleError::WrongCompanion(CompanionKind::Melee)));
+    }
+
+    #[test]
+    fn noxious_loss_of_the_last_card_waits_for_the_reshuffle() {
+        let mut game = board(&[(9, Some(Ability::Noxious), &[ToSlay::Range])], &[1, 2]);
+        game.noxious = NoxiousDiscard::Random;
+        game.deck.clear();
+        game.reshuffles = 1;
+        game.discard_pile = (0..12)
+            .map(|_| DiscardedCard { card: 3, turn: 1, action: Action::Swap, target: None })
+            .collect();
+
+        // Moving onto the Noxious monster spends one card and loses the other
+        let outcome = game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(outcome.noxious_discard.is_some());
+        assert!(game.hand.is_empty());
+        assert!(!outcome.game_over, "\n{}", game);
+        assert!(!game.is_over());
+
+        let outcome = game.apply(Command::EndTurn).unwrap();
+        assert!(outcome.turn_ended);
+        assert_eq!(game.reshuffles, 0);
+        assert_eq!(game.hand.len(), game.hand_limit as usize, "\n{}", game);
+        assert_eq!(game.deck.len(), 14 - game.hand.len());
+        assert!(game.discard_pile.is_empty());
+    }
+
+    #[test]
+    fn game_ends_without_cards_or_reshuffles() {
+        let mut game = board(&[plain(9, &[ToSlay::Range])], &[1]);
+        game.deck.clear();
+        game.reshuffles = 0;
+
+        let outcome = game.apply(play(Action::Swap, 0)).unwrap();
+        assert!(outcome.game_over);
+        assert!(game.is_over());
+        assert!(!game.is_won());
+        assert_eq!(game.apply(Command::EndTurn), Err(RuleError::GameOver));
+    }
+
+    #[test]
+    fn discard_pile_records_why_each_card_left() {
+        let mut game = board(&[
+            plain(9, &[ToSlay::Range]),
+            plain(9, &[ToSlay::Range]),
+            plain(1, &[ToSlay::Melee, ToSlay::Range]),
+        ], &[1, 1, 2, 3, 4]);
+        game.hand_limit = 6;
+
+        game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        game.apply(play(Action::Range(CHARACTER, RIGHT), 1)).unwrap();
+        game.apply(play(Action::Swap, 0)).unwrap();
+        game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        game.apply(play(Action::Melee(CHARACTER), 0)).unwrap();
+
+        let reasons: Vec<(u8, Action, Option<usize>)> = game.discard_pile.iter()
+            .map(|discarded| (discarded.card, discarded.action, discarded.target))
+            .collect();
+        assert_eq!(reasons, vec![
+            (1, Action::Move(CHARACTER, RIGHT), Some(1)),
+            (2, Action::Range(CHARACTER, RIGHT), Some(3)),
+            (1, Action::Swap, None),
+            (3, Action::Move(CHARACTER, RIGHT), Some(3)),
+            (4, Action::Melee(CHARACTER), Some(3)),
+        ]);
+        assert!(game.discard_pile.iter().all(|discarded| discarded.turn == 1));
+        assert!(game.discard_pile.iter().all(|discarded| !discarded.is_noxious_loss()));
+    }
+
+    #[test]
+    fn noxious_losses_target_the_noxious_monster() {
+        for &noxious in [NoxiousDiscard::Random, NoxiousDiscard::Chosen].iter() {
+            let mut game = board(&[
+                plain(9, &[ToSlay::Range]),
+                (9, Some(Ability::Noxious), &[ToSlay::Range]),
+            ], &[2, 4, 5]);
+            game.noxious = noxious;
+
+            let outcome = game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+            if noxious == NoxiousDiscard::Chosen {
+                assert!(outcome.discard_pending);
+                assert_eq!(game.apply(play(Action::Swap, 0)), Err(RuleError::DiscardPending));
+
+                let outcome = game.apply(play(Action::Discard, 1)).unwrap();
+                assert_eq!(outcome.noxious_discard, Some(5));
+                assert_eq!(outcome.card, None);
+            }
+
+            assert_eq!(game.hand.len(), 1);
+            let lost = &game.discard_pile[1];
+            assert!(lost.is_noxious_loss());
+            assert_eq!(lost.target, Some(2));
+            assert_eq!(game.discard_pile[0].target, Some(2));
+        }
+    }
+
+    #[test]
+    fn zero_cannot_move_or_shoot() {
+        let mut game = board(&[plain(0, &[ToSlay::Move, ToSlay::Range, ToSlay::Melee])], &[0, 3]);
+        game.player_index = 1;
+        let before = game.to_string();
+
+        for &action in [
+            Action::Move(CHARACTER, RIGHT),
+            Action::Move(CHARACTER, Direction::Left),
+            Action::Range(CHARACTER, RIGHT),
+            Action::Range(CHARACTER, Direction::Left),
+        ].iter() {
+            assert_eq!(game.apply(play(action, 0)), Err(RuleError::ZeroDistance));
+            assert_eq!(game.to_string(), before);
+            assert!(game.discard_pile.is_empty());
+        }
+
+        // From the deck slot too, where moving left would otherwise land on the first monster
+        game.player_index = 0;
+        assert_eq!(game.apply(play(Action::Move(CHARACTER, Direction::Left), 0)),
+                   Err(RuleError::ZeroDistance));
+
+        // A 0 still fights a monster of strength 0
+        game.player_index = 1;
+        let outcome = game.apply(play(Action::Melee(CHARACTER), 0)).unwrap();
+        assert_eq!(outcome.hit, Some(ToSlay::Melee));
+        assert!(outcome.trophy);
+    }
+
+    #[test]
+    fn card_out_of_range_is_rejected() {
+        let mut game = board(&[plain(1, &[ToSlay::Melee])], &[1, 2]);
+        assert_rejected(&mut game, play(Action::Swap, 2),
+                        RuleError::CardOutOfRange { index: 2, hand_size: 2 });
+    }
+
+    #[test]
+    fn dead_monsters_cannot_be_attacked() {
+        let mut game = board(&[plain(1, &[ToSlay::Melee]), plain(1, &[ToSlay::Range])], &[1, 2]);
+        game.monsters.alive[1] = false;
+
+        assert_rejected(&mut game, play(Action::Range(CHARACTER, RIGHT), 0),
+                        RuleError::MonsterDead { index: 1 });
+
+        game.player_index = 1;
+        assert_rejected(&mut game, play(Action::Melee(CHARACTER), 1),
+                        RuleError::MonsterDead { index: 1 });
+    }
+
+    #[test]
+    fn finished_games_reject_every_command() {
+        let mut game = board(&[plain(1, &[ToSlay::Melee])], &[1, 2]);
+        game.monsters.alive[1] = false;
+        assert!(game.is_over());
+
+        assert_rejected(&mut game, Command::EndTurn, RuleError::GameOver);
+        assert_rejected(&mut game, play(Action::Swap, 0), RuleError::GameOver);
+    }
+
+    #[test]
+    fn noxious_discards_come_first_and_only_when_pending() {
+        let mut game = board(&[(9, Some(Ability::Noxious), &[ToSlay::Range])], &[1, 2, 3]);
+        game.noxious = NoxiousDiscard::Chosen;
+        assert_rejected(&mut game, play(Action::Discard, 0), RuleError::NothingToDiscard);
+
+        game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(game.discard_pending);
+        assert_rejected(&mut game, play(Action::Swap, 0), RuleError::DiscardPending);
+        assert_rejected(&mut game, Command::EndTurn, RuleError::DiscardPending);
+    }
+
+    #[test]
+    fn invalid_rules_cannot_create_a_game() {
+        let rules = Rules { dungeon_size: 1, ..Rules::default() };
+        assert!(matches!(GameState::with_rules(0, &rules),
+                         Err(RuleError::InvalidConfiguration(ConfigError::DungeonSize { .. }))));
+    }
+
+    #[test]
+    fn strict_mode_rejects_wasted_plays() {
+        let mut game = board(&[
+            plain(3, &[ToSlay::Melee, ToSlay::Range]),
+            plain(1, &[ToSlay::Move]),
+        ], &[1, 2, 5]);
+        game.strict = true;
+
+        // From the deck, shooting left leaves the dungeon and fighting hits nothing
+        assert_rejected(&mut game, play(Action::Range(CHARACTER, Direction::Left), 0),
+                        RuleError::OutOfReach);
+        assert_rejected(&mut game, play(Action::Melee(CHARACTER), 0), RuleError::OutOfReach);
+        assert_rejected(&mut game, play(Action::Range(CHARACTER, RIGHT), 2),
+                        RuleError::OutOfReach);
+
+        game.player_index = 1;
+        assert_rejected(&mut game, play(Action::Melee(CHARACTER), 1),
+                        RuleError::TooWeak { index: 1, card: 2, strength: 3 });
+        assert_rejected(&mut game, play(Action::Range(CHARACTER, RIGHT), 0),
+                        RuleError::NotRequired { index: 2, to_slay: ToSlay::Range });
+
+        // Outside strict mode the same plays spend the card for nothing
+        game.strict = false;
+        let outcome = game.apply(play(Action::Melee(CHARACTER), 1)).unwrap();
+        assert_eq!(outcome.hit, None);
+        assert_eq!(game.hand, vec![1, 5]);
+    }
+
+    #[test]
+    fn preview_explains_without_changing_the_game() {
+        let mut game = board(&[plain(3, &[ToSlay::Melee])], &[2, 3]);
+        game.player_index = 1;
+        let before = serde_json::to_string(&game).unwrap();
+
+        assert_eq!(game.preview(play(Action::Melee(CHARACTER), 0)),
+                   Err(RuleError::TooWeak { index: 1, card: 2, strength: 3 }));
+        let outcome = game.preview(play(Action::Melee(CHARACTER), 1)).unwrap();
+        assert!(outcome.killed && outcome.trophy);
+        assert_eq!(serde_json::to_string(&game).unwrap(), before);
+    }
+
+    #[test]
+    fn reign_protects_until_the_stronger_neighbour_is_slain() {
+        let mut game = board(&[
+            plain(3, &[ToSlay::Range]),
+            (2, Some(Ability::Reign), &[ToSlay::Range]),
+        ], &[1, 2, 2, 4]);
+        assert_eq!(game.monsters.reign_blockers(2), vec![1]);
+
+        let outcome = game.apply(play(Action::Range(CHARACTER, RIGHT), 1)).unwrap();
+        assert_eq!(outcome.hit, Some(ToSlay::Range));
+        assert!(outcome.trophy);
+        assert!(!outcome.killed);
+        assert!(game.monsters.alive[2]);
+
+        let outcome = game.apply(play(Action::Range(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(outcome.killed && !outcome.trophy);
+        assert!(!game.monsters.reign(2));
+
+        let outcome = game.apply(play(Action::Range(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(outcome.killed, "\n{}", game);
+        assert!(outcome.game_over && game.is_won());
+    }
+
+    #[test]
+    fn rally_strengthens_neighbours_while_alive() {
+        let mut game = board(&[
+            plain(2, &[ToSlay::Melee]),
+            (1, Some(Ability::Rally), &[ToSlay::Range]),
+            plain(2, &[ToSlay::Melee]),
+        ], &[1, 2, 3, 5]);
+        game.player_index = 1;
+        assert_eq!(game.monsters.strength(1), Ok(3));
+        assert_eq!(game.monsters.strength(3), Ok(3));
+
+        // A melee below the strength misses, while one at the strength slays and earns a trophy
+        let outcome = game.apply(play(Action::Melee(CHARACTER), 1)).unwrap();
+        assert_eq!(outcome.hit, None);
+        assert!(!outcome.killed && game.monsters.alive[1]);
+
+        let outcome = game.apply(play(Action::Melee(CHARACTER), 1)).unwrap();
+        assert!(outcome.killed && outcome.trophy);
+
+        let outcome = game.apply(play(Action::Range(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(outcome.killed && outcome.trophy);
+        assert_eq!(game.monsters.strength(3), Ok(2));
+        assert_eq!(game.trophies, 2);
+    }
+
+    #[test]
+    fn hits_only_count_within_a_turn() {
+        let mut game = board(&[
+            plain(2, &[ToSlay::Move, ToSlay::Melee]),
+            plain(9, &[ToSlay::Range]),
+        ], &[1, 2, 2, 4]);
+
+        let outcome = game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        assert_eq!(outcome.hit, Some(ToSlay::Move));
+        assert!(!outcome.killed && !outcome.trophy);
+
+        game.apply(Command::EndTurn).unwrap();
+        assert!(game.monsters.current_hits.iter().all(|hits| hits.is_empty()));
+        game.hand = vec![2, 2, 3];
+
+        // The Move hit of the last turn no longer counts
+        let outcome = game.apply(play(Action::Melee(CHARACTER), 0)).unwrap();
+        assert!(!outcome.killed && outcome.trophy);
+
+        // Stepping off and back on again deals the missing Move hit
+        game.apply(play(Action::Move(CHARACTER, RIGHT), 0)).unwrap();
+        assert_eq!(game.player_index, 2);
+        let outcome = game.apply(play(Action::Move(CHARACTER, Direction::Left), 0)).unwrap();
+        assert_eq!(game.player_index, 1);
+        assert!(outcome.killed, "\n{}", game);
+    }
+
+    #[test]
+    fn end_of_turn_refills_the_hand() {
+        let mut game = board(&[plain(9, &[ToSlay::Range])], &[4]);
+        let deck = game.deck.len();
+
+        let outcome = game.apply(Command::EndTurn).unwrap();
+        assert!(outcome.turn_ended && !outcome.transformed);
+        assert_eq!(game.turn, 2);
+        assert_eq!(game.hand.len(), game.hand_limit as usize);
+        assert_eq!(game.deck.len(), deck + 1 - game.hand_limit as usize);
+        assert!(game.hand.windows(2).all(|pair| pair[0] <= pair[1]), "\n{}", game);
+    }
+
+    #[test]
+    fn playing_the_whole_hand_transforms_once() {
+        let mut game = board(&[plain(9, &[ToSlay::Range])], &[1, 2]);
+        game.hand_limit = 2;
+
+        game.apply(play(Action::Swap, 0)).unwrap();
+        let outcome = game.apply(play(Action::Swap, 0)).unwrap();
+        assert!(outcome.transformed && outcome.turn_ended);
+        assert_eq!(game.player_kind, PlayerKind::Monstrous);
+        assert_eq!(game.hand_limit, 3);
+        assert_eq!(game.hand.len(), 3);
+
+        for _ in 0..3 {
+            let outcome = game.apply(play(Action::Swap, 0)).unwrap();
+            assert!(!outcome.transformed);
+        }
+        assert_eq!(game.hand_limit, 3);
+    }
+
+    #[test]
+    fn winning_ends_the_game_with_the_payments() {
+        let mut game = board(&[plain(2, &[ToSlay::Range])], &[1, 2, 3]);
+        let outcome = game.apply(play(Action::Range(CHARACTER, RIGHT), 0)).unwrap();
+        assert!(outcome.killed && outcome.game_over);
+        assert!(game.is_won());
+        assert_eq!(game.score(), game.payments * 3 + (game.hand.len() + game.deck.len()) as u32);
+        assert_eq!(game.apply(Command::EndTurn), Err(RuleError::GameOver));
+    }
+}
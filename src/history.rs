@@ -0,0 +1,178 @@
+//! Undo/redo stack of game snapshots
+
+use crate::engine::Outcome;
+
+/// Stack of snapshots taken before each change, along with the snapshots that were undone
+#[derive(Debug, Clone)]
+pub struct History<T> {
+    /// Snapshots taken before each change, most recent last
+    undo: Vec<T>,
+
+    /// Snapshots that were undone and can be redone, most recent last
+    redo: Vec<T>,
+
+    /// Snapshots are recorded at all. Ironman games are played without undo.
+    enabled: bool,
+}
+
+impl<T> History<T> {
+    pub fn new() -> History<T> {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+            enabled: true,
+        }
+    }
+
+    /// History that never records a snapshot, so nothing can be undone, for ironman games
+    pub fn disabled() -> History<T> {
+        History {
+            enabled: false,
+            ..History::new()
+        }
+    }
+
+    /// Record the `snapshot` taken just before a change. Any undone changes can no longer be
+    /// redone.
+    pub fn push(&mut self, snapshot: T) {
+        if self.enabled {
+            self.undo.push(snapshot);
+        }
+        self.redo.clear();
+    }
+
+    /// Record the `snapshot` taken just before a command that led to `outcome`. Only card plays
+    /// within the current turn can be undone, so ending the turn or the game forgets every
+    /// snapshot.
+    pub fn record(&mut self, snapshot: T, outcome: &Outcome) {
+        if outcome.turn_ended || outcome.game_over {
+            self.clear();
+        } else {
+            self.push(snapshot);
+        }
+    }
+
+    /// Forget every snapshot, i.e. when the changes can no longer be taken back
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    /// Returns `true` if there is a change to undo
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    /// Returns `true` if there is an undone change to redo
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Replace `current` with the snapshot before the last change. Returns `false` if there
+    /// is nothing to undo.
+    pub fn undo(&mut self, current: &mut T) -> bool {
+        match self.undo.pop() {
+            Some(previous) => {
+                self.redo.push(std::mem::replace(current, previous));
+                true
+            }
+            None => false
+        }
+    }
+
+    /// Replace `current` with the snapshot of the last undone change. Returns `false` if there
+    /// is nothing to redo.
+    pub fn redo(&mut self, current: &mut T) -> bool {
+        match self.redo.pop() {
+            Some(next) => {
+                self.undo.push(std::mem::replace(current, next));
+                true
+            }
+            None => false
+        }
+    }
+}
+
+impl<T> Default for History<T> {
+    fn default() -> History<T> {
+        History::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::*;
+
+    /// Apply `command` to `game`, recording the snapshot taken before it in `history`
+    fn play(history: &mut History<GameState>, game: &mut GameState, command: Command) -> Outcome {
+        let snapshot = game.clone();
+        let outcome = game.apply(command).unwrap_or_else(|e| panic!("{}\n{}", e, game));
+        history.record(snapshot, &outcome);
+        outcome
+    }
+
+    const SWAP: Command = Command::Play(Action::Swap, 0);
+
+    #[test]
+    fn undo_and_redo_restore_the_snapshots() {
+        let mut history = History::new();
+        let mut game = GameState::new(5);
+        let start = game.hand.clone();
+
+        play(&mut history, &mut game, SWAP);
+        let after = game.hand.clone();
+        assert!(history.can_undo());
+        assert!(!history.can_redo());
+
+        assert!(history.undo(&mut game));
+        assert_eq!(game.hand, start);
+        assert!(!history.undo(&mut game));
+
+        assert!(history.redo(&mut game));
+        assert_eq!(game.hand, after);
+        assert!(!history.redo(&mut game));
+    }
+
+    #[test]
+    fn new_play_clears_redo() {
+        let mut history = History::new();
+        let mut game = GameState::new(5);
+
+        play(&mut history, &mut game, SWAP);
+        play(&mut history, &mut game, SWAP);
+        assert!(history.undo(&mut game));
+        assert!(history.can_redo());
+
+        play(&mut history, &mut game, SWAP);
+        assert!(!history.can_redo());
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn end_of_turn_clears_history() {
+        let mut history = History::new();
+        let mut game = GameState::new(5);
+
+        play(&mut history, &mut game, SWAP);
+        play(&mut history, &mut game, SWAP);
+        assert!(history.undo(&mut game));
+
+        let outcome = play(&mut history, &mut game, Command::EndTurn);
+        assert!(outcome.turn_ended);
+        assert!(!history.can_undo());
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn ironman_disables_undo() {
+        let mut history = History::disabled();
+        let mut game = GameState::new(5);
+
+        play(&mut history, &mut game, SWAP);
+        let hand = game.hand.clone();
+        assert!(!history.can_undo());
+        assert!(!history.undo(&mut game));
+        assert_eq!(game.hand, hand);
+    }
+}
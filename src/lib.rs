@@ -0,0 +1,21 @@
+//! Rules for the Maverick card game, independent of any frontend
+
+#[macro_use]
+extern crate log;
+
+pub mod consts;
+pub mod engine;
+pub mod history;
+pub mod moves;
+pub mod replay;
+pub mod rng;
+pub mod rules;
+pub mod save;
+pub mod scores;
+pub mod script;
+pub mod simulate;
+pub mod solver;
+pub mod stats;
+pub mod strategy;
+pub mod tracker;
+mod util;
@@ -1,14 +1,24 @@
-#![feature(exclusive_range_pattern)]
-#![feature(vec_remove_item)]
-
 use quicksilver::{
-    geom::{Rectangle, Vector},
+    geom::{Rectangle, Transform, Vector},
     geom::Shape,
     graphics::{Color, Image, VectorFont},
     input::*,
-    run, Graphics, Input, Result, Settings, Window,
+    run, Graphics, Input, Result, Settings, Timer, Window,
 };
 
+use maverick::consts::*;
+use maverick::engine::*;
+use maverick::history::History;
+use maverick::moves::legal_moves;
+use maverick::replay::Replay;
+use maverick::rules::{Difficulty, Rules, RulesError, DIFFICULTIES};
+use maverick::save::SaveGame;
+use maverick::scores::{ScoreFilter, ScoreTable};
+use maverick::script::parse_command;
+use maverick::stats::{GameEvent, LifetimeStats};
+use maverick::strategy::{self, Scripted, Strategy};
+use maverick::tracker::DeckTracker;
+
 use std::collections::HashMap;
 
 #[macro_use]
@@ -16,184 +26,220 @@ extern crate log;
 
 mod assets;
 
-mod consts;
-use consts::*;
+const USAGE: &str = "\
+Usage: maverick [[--seed] SEED] [--difficulty NAME] [--dungeon SIZE] [--strict] [--ironman]
+                [--bot NAME] [--window WIDTHxHEIGHT] [--log LEVEL] [--replay FILE]
+                [--headless SCRIPT]";
 
-fn main() {
-    run(
-        Settings {
-            size: Vector::new(2048.0, 1024.0),
-            title: "Maverick",
-            log_level: log::Level::Info,
-            ..Settings::default()
-        },
-        app,
-    );
-}
+/// Command line options of the game
+#[derive(Debug, Clone)]
+struct Args {
+    /// Seed of the first game. Every reset afterwards starts from a fresh random seed.
+    seed: Option<u64>,
 
-/// Which entity an action can be performed on.
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Entity {
-    Character,
-    Companion
-}
+    /// Difficulty of every new game, skipping the difficulty menu
+    difficulty: Option<Difficulty>,
 
-/// Direction which an ability is performed in the dungeon row
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Direction {
-    Left,
-    Right
-}
+    /// Number of slots in the dungeon row, overriding the difficulty
+    dungeon_size: Option<usize>,
 
-/// Available actions the player can perform in the game
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum Action {
-    Range(Entity, Direction),
-    Melee(Entity),
-    Move(Entity, Direction),
-    Swap,
-    EndTurn
-}
+    /// Reject card plays that would be wasted
+    strict: bool,
 
-/// Special abilities that some monsters have
-#[derive(Debug, Copy, Clone)]
-enum Ability {
-    Noxious,
-    Rally,
-    Reign
-}
+    /// Disable undo for every game
+    ironman: bool,
 
-/// Actions needed to be performed on a monster in order to kill it
-#[derive(Debug, Copy, Clone, PartialEq, Eq)]
-enum ToSlay {
-    Melee,
-    Range,
-    Move
-}
+    /// Built-in strategy playing instead of the mouse
+    bot: Option<String>,
 
-/// Monster stats
-struct Monsters {
-    images: Vec<Image>,
-    names: Vec<&'static str>,
-    strengths: Vec<u8>,
-    strength_adjustments: Vec<u8>,
-    abilities: Vec<Option<Ability>>,
-    to_slays: Vec<Vec<ToSlay>>,
-    current_hits: Vec<Vec<ToSlay>>,
-    alive: Vec<bool>
-}
+    /// Size of the window
+    window_size: Vector,
 
-impl Monsters {
-    /// Return the current strength of the given index by adding the adjustment to the base strength
-    fn strength(&self, index: usize) -> u8 {
-        assert!(index < MONSTER_DECK_SIZE, "Attempted to get strength out of bounds: {} of {}", index, MONSTER_DECK_SIZE);
+    /// Most verbose level of the messages logged
+    log_level: log::Level,
 
-        self.strengths[index] + self.strength_adjustments[index]
-    }
+    /// Replay file to play back as the first game, once it is verified
+    replay: Option<String>,
+
+    /// Command file to play without opening a window
+    headless: Option<String>,
 }
 
-type MonsterStats = (&'static str, u8, Option<Ability>, [Option<ToSlay>; 3]);
-
-
-/// Monster stats for the available monsters
-const MONSTER_STATS: [MonsterStats; 18] = [
-    ("Banshee",    1, None,                   [Some(ToSlay::Melee), Some(ToSlay::Range), None]),
-    ("Beholder",   1, None,                   [Some(ToSlay::Range), Some(ToSlay::Range), Some(ToSlay::Range)]),
-    ("Bug",        1, None,                   [Some(ToSlay::Range), None, None]),
-    ("Demon",      5, None,                   [Some(ToSlay::Melee), Some(ToSlay::Range), None]),
-    ("Dragon",     5, Some(Ability::Reign),   [Some(ToSlay::Move), Some(ToSlay::Melee), Some(ToSlay::Range)]),
-    ("Elemental",  2, None,                   [Some(ToSlay::Melee), Some(ToSlay::Range), None]),
-    ("Ghost",      0, Some(Ability::Noxious), [Some(ToSlay::Move), None, None]),
-    ("Golem",      3, Some(Ability::Reign),   [Some(ToSlay::Melee), None, None]),
-    ("Hellhound",  2, None,                   [Some(ToSlay::Range), None, None]),
-    ("Howler",     4, Some(Ability::Rally),   [Some(ToSlay::Melee), None, None]),
-    ("Imp",        0, None,                   [Some(ToSlay::Move), Some(ToSlay::Move), None]),
-    ("Lich",       4, Some(Ability::Reign),   [Some(ToSlay::Range), None, None]),
-    ("Scorpion",   1, Some(Ability::Noxious), [Some(ToSlay::Melee), Some(ToSlay::Range), None]),
-    ("Skeleton",   2, None,                   [Some(ToSlay::Move), Some(ToSlay::Melee), None]),
-    ("Spider",     1, Some(Ability::Noxious), [Some(ToSlay::Range), Some(ToSlay::Range), None]),
-    ("Troglodyte", 1, Some(Ability::Rally),   [Some(ToSlay::Move), Some(ToSlay::Melee), None]),
-    ("Troll",      3, None,                   [Some(ToSlay::Move), Some(ToSlay::Range), None]),
-    ("Werewolf",   2, None,                   [Some(ToSlay::Melee), Some(ToSlay::Melee), None]),
-];
-
-impl Monsters {
-    /// Initialize the monster deck for this game. `Graphics` is needed to create 
-    /// the image for each monster.
-    pub async fn init(gfx: &Graphics) -> Result<Monsters> {
-        // Create the monster deck via a random selection of 13 monsters
-        let mut monster_indexes = Vec::new();
-        loop {
-            if monster_indexes.len() == MONSTER_DECK_SIZE {
-                break;
+impl Args {
+    /// Parse the command line arguments
+    fn parse() -> std::result::Result<Args, String> {
+        let mut parsed = Args {
+            seed: None,
+            difficulty: None,
+            dungeon_size: None,
+            strict: false,
+            ironman: false,
+            bot: None,
+            window_size: Vector::new(2048.0, 1024.0),
+            log_level: log::Level::Info,
+            replay: None,
+            headless: None,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            // Flags without a value
+            match arg.as_str() {
+                "--strict" => { parsed.strict = true; continue; }
+                "--ironman" => { parsed.ironman = true; continue; }
+                _ => {}
             }
 
-            let mut index = rand::random::<usize>() % MONSTER_STATS.len();
-            loop {
-                if !monster_indexes.contains(&index) {
-                    monster_indexes.push(index);
-                    break;
+            if !arg.starts_with("--") {
+                parsed.seed = Some(arg.parse().map_err(|_| format!("Invalid seed {}", arg))?);
+                continue;
+            }
+
+            let value = args.next().ok_or_else(|| format!("Missing value for {}", arg))?;
+            let invalid = || format!("Invalid value for {}: {}", arg, value);
+            match arg.as_str() {
+                "--seed" => parsed.seed = Some(value.parse().map_err(|_| invalid())?),
+                "--difficulty" => parsed.difficulty = Some(Difficulty::by_name(&value)
+                    .ok_or_else(|| format!("Unknown difficulty {}, expected one of {:?}",
+                                           value, DIFFICULTIES))?),
+                "--dungeon" => parsed.dungeon_size = Some(value.parse().map_err(|_| invalid())?),
+                "--bot" => parsed.bot = Some(value),
+                "--window" => {
+                    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+                    let width: f32 = width.parse().map_err(|_| invalid())?;
+                    let height: f32 = height.parse().map_err(|_| invalid())?;
+                    parsed.window_size = Vector::new(width, height);
                 }
+                "--log" => parsed.log_level = value.parse().map_err(|_| invalid())?,
+                "--replay" => parsed.replay = Some(value),
+                "--headless" => parsed.headless = Some(value),
+                _ => return Err(format!("Unknown argument {}", arg)),
+            }
+        }
 
-                index = rand::random::<usize>() % MONSTER_STATS.len();
+        // A headless game never opens the window the replay would be played back in
+        if parsed.replay.is_some() && parsed.headless.is_some() {
+            return Err("--replay and --headless cannot be used together".to_string());
+        }
+
+        Ok(parsed)
+    }
+
+    /// Rules of a new game at `difficulty` from the `loaded` rules
+    fn rules(&self, loaded: &Rules, difficulty: Difficulty)
+            -> std::result::Result<Rules, RulesError> {
+        let mut rules = rules_for(loaded, difficulty, self.dungeon_size)?;
+        rules.strict = self.strict;
+        Ok(rules)
+    }
+}
+
+fn main() {
+    let args = match Args::parse() {
+        Ok(args) => args,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(script) = &args.headless {
+        std::process::exit(headless(&args, script));
+    }
+
+    // Only play back replays that reproduce their recorded results
+    let playback = match &args.replay {
+        Some(path) => match Replay::load(path).and_then(|replay| replay.verify().map(|_| replay)) {
+            Ok(replay) => Some(replay),
+            Err(e) => {
+                eprintln!("Cannot play back {}: {}", path, e);
+                std::process::exit(1);
             }
+        },
+        None => None
+    };
+
+    run(
+        Settings {
+            size: args.window_size,
+            title: "Maverick",
+            log_level: args.log_level,
+            ..Settings::default()
+        },
+        move |window, gfx, input| app(window, gfx, input, args, playback),
+    );
+}
+
+/// Play the commands in the `script` file, one per line, without opening a window. Blank lines
+/// and lines starting with `#` are skipped. Prints the final board and score, returning the
+/// exit code of the process: 1 if the rules or a command are invalid or the game is not over at
+/// the end of the script.
+fn headless(args: &Args, script: &str) -> i32 {
+    let source = match std::fs::read_to_string(script) {
+        Ok(source) => source,
+        Err(e) => {
+            eprintln!("Failed to read {}: {}", script, e);
+            return 1;
         }
+    };
 
-        // Init the monsters struct 
-        let mut monsters = Monsters {
-            images: Vec::new(),
-            names: Vec::new(),
-            strengths: Vec::new(),
-            strength_adjustments: Vec::new(),
-            abilities: Vec::new(),
-            to_slays: Vec::new(),
-            current_hits: Vec::new(),
-            alive: Vec::new(),
-        };
-        
-        // The first empty element is the deck itself
-        monsters.images.push(Image::load(&gfx, "action.png").await?);
-        monsters.names.push("Deck");
-        monsters.strengths.push(0);
-        monsters.strength_adjustments.push(0);
-        monsters.abilities.push(None);
-        monsters.to_slays.push(Vec::new());
-        monsters.current_hits.push(Vec::new());
-        monsters.alive.push(false);
-
-
-        // Populate the Monsters struct
-        for &index in &monster_indexes {
-            // Get the monster stats for the current monster
-            let (name, strength, ability, to_slay) = MONSTER_STATS[index];
-
-            // Get the monster image
-            info!("Getting image: {}", name);
-            let image = Image::load(&gfx, format!("monsters_small/{}.png", name)).await?;
-            monsters.images.push(image);
+    let loaded = match Rules::load(ASSET_DIR) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("Failed to load rules: {}", e);
+            return 1;
+        }
+    };
+
+    let seed = args.seed.unwrap_or_else(rand::random);
+    let rules = match args.rules(&loaded, args.difficulty.unwrap_or_default()) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("Invalid rules: {}", e);
+            return 1;
+        }
+    };
 
-            // Populate these monster fields
-            monsters.names.push(name);
-            monsters.strengths.push(strength);
-            monsters.strength_adjustments.push(0);
-            monsters.abilities.push(ability);
-            monsters.alive.push(true);
+    let mut game = match GameState::with_rules(seed, &rules) {
+        Ok(game) => game,
+        Err(e) => {
+            eprintln!("Cannot start game: {}", e);
+            return 1;
+        }
+    };
 
-            // Create a Vec from only the valid ToSlay
-            let curr_slay: Vec<ToSlay> = to_slay.iter()
-                                                .filter(|x| x.is_some())
-                                                .map(|x| x.unwrap())
-                                                .collect();
+    for (number, line) in source.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
 
-            // Add the allocated vec to the Monsters
-            monsters.to_slays.push(curr_slay);
+        let command = match parse_command(line) {
+            Some(command) => command,
+            None => {
+                eprintln!("{}:{}: unknown command {}", script, number + 1, line);
+                return 1;
+            }
+        };
 
-            // Init the current hits for each monster 
-            monsters.current_hits.push(Vec::new());
+        if let Err(e) = game.apply(command) {
+            eprintln!("{}:{}: {}\n{}", script, number + 1, e, game);
+            return 1;
         }
+    }
+
+    print!("{}", game);
+    println!("Score: {} ({})", game.score(),
+             if game.is_won() { "won" } else if game.is_over() { "lost" } else { "not over" });
 
-        Ok(monsters)
+    // A script that stops early did not play the game it was meant to
+    if !game.is_over() {
+        eprintln!("{}: the script ended before the game was over", script);
+        return 1;
     }
+
+    0
 }
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
@@ -212,24 +258,13 @@ enum AssetType {
     CardBack
 }
 
-/// Current player kind. Player starts as `Regular` and shifts to `Monstrous` if 5 actions are
-/// spent on any one turn
-enum PlayerKind {
-    Regular,
-    Monstrous
-}
-
-/// Types of companions available
-enum CompanionKind {
-    Melee,
-    Range
-}
-
 /// States of the game itself
 #[derive(Debug, Copy, Clone)]
 enum State {
     Playing,
     EndGame,
+    HighScores,
+    Statistics,
     Reset
 }
 
@@ -238,7 +273,17 @@ enum State {
 enum ClickableType {
     Action(Action),
     Card(usize),
-    State(State)
+    State(State),
+    Undo,
+    Redo,
+    Discards
+}
+
+/// Action chosen from the keyboard that still needs an arrow key for its direction
+#[derive(Debug, Copy, Clone)]
+enum Directional {
+    Move(Entity),
+    Range(Entity)
 }
 
 /// Global struct for handling Game State
@@ -246,20 +291,20 @@ struct Game {
     /// Current game state of the game
     state: State,
 
-    /// Monsters in this game
-    monsters: Monsters,
+    /// Rules state of the current game
+    game_state: GameState,
 
-    /// Current index of the player
-    player_index: usize,
+    /// Log of every command applied to the current game
+    replay: Replay,
 
-    /// Type of player currently active
-    player_kind: PlayerKind,
+    /// Snapshots of the game and its replay before each card played this turn
+    history: History<(GameState, Replay)>,
 
-    /// Current index of the companion
-    companion_index: usize,
+    /// Ironman runs cannot undo card plays
+    ironman: bool,
 
-    /// Type of companion currently active
-    companion_kind: CompanionKind,
+    /// Images for each monster in the dungeon row, in the same order as `Monsters`
+    monster_images: Vec<Image>,
 
     /// Image cache
     images: HashMap<AssetType, Image>,
@@ -271,36 +316,70 @@ struct Game {
     /// to query whether a mouse click should trigger an action
     clickables: Vec<(Rectangle, ClickableType)>,
 
-    /// Deck containing the action cards
-    deck: Vec<u8>,
-
-    /// Current hand
-    hand: Vec<u8>,
-
-    /// Maximum hand size: 5 for regular player and 6 when player becomes Monstrous by playing
-    /// 5 actions in one turn
-    hand_limit: u8,
-
     /// Currently selected action
     current_action: Option<Action>,
 
     /// Index of the card currently selected
     current_card: Option<usize>,
 
-    /// Has a card been discarded in a turn. Used when checking if to transform to Monstrous form
-    discarded: bool,
+    /// Factor the board is scaled by to fit the dungeon row in the window. Updated every
+    /// `draw()` call.
+    scale: f32,
 
-    /// Number of cards withheld at the beginning of the game. Worth 3 points each if game is won.
-    payments: u32,
+    /// Explanation of why the last command was rejected, shown until the next command succeeds
+    message: Option<String>,
 
-    /// Trophies gathered during the course of the game
-    trophies: u32,
+    /// Last location of the pointer in window coordinates
+    pointer: Vector,
+
+    /// Region of every slot of the dungeon row in board coordinates. This cache is updated
+    /// every `draw()` call to find the monster under the pointer
+    monster_regions: Vec<Rectangle>,
+
+    /// The discard pile is shown over the board
+    show_discards: bool,
+
+    /// Action chosen from the keyboard waiting for its direction
+    directional: Option<Directional>,
+
+    /// Progress is written to `SAVE_FILE` and `REPLAY_DIR`. Off while playing back a replay.
+    saving: bool,
+
+    /// The game is played by a built-in strategy, so it is left out of the player's statistics
+    bot: bool,
 }
 
 impl Game {
-    pub async fn init(gfx: &Graphics) -> Result<Game> {
-        // Create the monster deck for this game
-        let monsters = Monsters::init(&gfx).await?;
+    /// Start a new game from `seed` using the given `rules`
+    pub async fn init(gfx: &Graphics, seed: u64, ironman: bool, rules: &Rules) -> Result<Game> {
+        // Create the rules state for this game
+        info!("Seed: {}", seed);
+        let (game_state, rules, message) = match GameState::with_rules(seed, rules) {
+            Ok(game_state) => (game_state, rules.clone(), None),
+            Err(e) => {
+                // Fall back to the default rules rather than refusing to start
+                error!("Cannot start game: {}", e);
+                (GameState::new(seed), Rules::default(), Some(format!("{}, using default rules", e)))
+            }
+        };
+
+        let mut replay = Replay::new(seed);
+        replay.ironman = ironman;
+        replay.rules = rules;
+
+        let mut game = Game::resume(gfx, game_state, replay).await?;
+        game.message = message;
+        Ok(game)
+    }
+
+    /// Continue the given game along with the replay recorded so far
+    pub async fn resume(gfx: &Graphics, game_state: GameState, replay: Replay) -> Result<Game> {
+        // Load the image for each monster in the dungeon, along with the deck in `DECK_SLOT`
+        let mut monster_images = Vec::new();
+        for (name, path) in game_state.monsters.names.iter().zip(&game_state.monsters.images) {
+            info!("Getting image: {}", name);
+            monster_images.push(Image::load(&gfx, path).await?);
+        }
 
         let mut images = HashMap::new();
 
@@ -309,11 +388,6 @@ impl Game {
             (AssetType::MonstrousPlayer, "characters_small/big_crop.png"),
             (AssetType::MeleeCompanion, "companions_small/melee_crop.png"),
             (AssetType::RangeCompanion, "companions_small/range_crop.png"),
-            (AssetType::Action(1),"actions_small/1black.png"),
-            (AssetType::Action(2),"actions_small/2black.png"),
-            (AssetType::Action(3),"actions_small/3black.png"),
-            (AssetType::Action(4),"actions_small/4black.png"),
-            (AssetType::Action(5),"actions_small/5black.png"),
             (AssetType::MeleeTarget,"targets/melee.png"),
             (AssetType::RangeTarget,"targets/range.png"),
             (AssetType::MoveTarget,"targets/move.png"),
@@ -324,68 +398,46 @@ impl Game {
             images.insert(*asset_type, Image::load(&gfx, &path).await?);
         }
 
-
-        let companion_kind = match rand::random::<u8>() & 1 {
-            0 => CompanionKind::Melee,
-            1 => CompanionKind::Range,
-            _ => unreachable!()
-        };
-
-        // Generate the deck itself
-        let mut deck = vec![
-            1, 1, 1, 1, 1, 1, 1, 1,  
-            2, 2, 2, 2, 2, 2, 2, 2,  
-            3, 3, 3, 3, 3, 3, 3, 3,  
-            4, 4, 4, 4, 4, 4, 4, 4,  
-            5, 5, 5, 5, 5, 5, 5, 5
-        ];
-
-        // Number of initial cards removed 
-        let payments = PAYMENTS;
-
-        // Shuffle the deck
-        for _ in 0..1000 {
-            let x = rand::random::<usize>() % deck.len();
-            let y = rand::random::<usize>() % deck.len();
-            if x == y {
-                continue;
+        // Load the image of every card in the deck the game was created with. Cards without an
+        // image are drawn as their value.
+        for card in &replay.rules.deck {
+            if let Some(path) = &card.image {
+                images.insert(AssetType::Action(card.value), Image::load(&gfx, path).await?);
             }
-            deck.swap(x, y);
-        }
-
-        // Discard cards equal to payment
-        for _ in 0..payments { deck.pop(); }
-
-        // Populate the initial hand
-        let mut hand = Vec::new();
-        for _ in 0..5 {
-            hand.push(deck.pop().unwrap());
         }
 
         Ok(Game {
             state: State::Playing,
-            monsters,
-            player_index: 0,
-            companion_index: 0,
+            ironman: replay.ironman,
+            history: if replay.ironman { History::disabled() } else { History::new() },
+            replay,
+            game_state,
+            monster_images,
             images,
-            player_kind: PlayerKind::Regular,
-            companion_kind,
             clickables: Vec::new(),
             font: VectorFont::load("iosevka-regular.ttf").await?,
-            deck,
-            hand,
-            hand_limit: 5,
             current_action: None,
             current_card: None,
-            discarded: false,
-            payments,
-            trophies: 0
+            scale: 1.0,
+            message: None,
+            pointer: Vector::ZERO,
+            monster_regions: Vec::new(),
+            show_discards: false,
+            directional: None,
+            saving: true,
+            bot: false,
         })
     }
 
     /// Draw the current game state using the given `Graphics`
     pub async fn draw(&mut self, window: &Window, mut gfx: &mut Graphics) 
             -> Result<()> {
+        // Shrink the whole board if the dungeon row is wider than the window
+        let slot_width = self.images[&AssetType::RegPlayer].size().x + PADDING;
+        let board_width = PADDING + slot_width * self.game_state.dungeon_size() as f32;
+        self.scale = (window.size().x / board_width).min(1.0);
+        gfx.set_transform(Transform::scale(Vector::new(self.scale, self.scale)));
+
         if matches!(self.state, State::EndGame) {
             gfx.clear(Color::BLACK);
             let mut font = self.font.to_renderer(&gfx, 48.0)?;
@@ -397,10 +449,10 @@ impl Game {
                 Vector::new(10.0, 100.0),
             )?;
 
-            let message = if self.monsters.alive.iter().any(|&x| x == true) {
-                "YOU LOST!"
-            } else {
+            let message = if self.game_state.is_won() {
                 "YOU WON!"
+            } else {
+                "YOU LOST!"
             };
 
             font.draw( 
@@ -421,6 +473,26 @@ impl Game {
             let fullscreen = Rectangle::new(Vector::new(5.0, 160.0), Vector::new(350.0, 50.0));
             self.clickables.push((fullscreen, ClickableType::State(State::Reset)));
 
+            font.draw( 
+                &mut gfx,
+                "High scores..",
+                Color::RED,
+                Vector::new(10.0, 250.0),
+            )?;
+
+            let high_scores = Rectangle::new(Vector::new(5.0, 210.0), Vector::new(350.0, 50.0));
+            self.clickables.push((high_scores, ClickableType::State(State::HighScores)));
+
+            font.draw( 
+                &mut gfx,
+                "Statistics..",
+                Color::RED,
+                Vector::new(410.0, 250.0),
+            )?;
+
+            let statistics = Rectangle::new(Vector::new(405.0, 210.0), Vector::new(350.0, 50.0));
+            self.clickables.push((statistics, ClickableType::State(State::Statistics)));
+
             font.draw( 
                 &mut gfx,
                 "Score:",
@@ -430,54 +502,86 @@ impl Game {
 
             font.draw( 
                 &mut gfx,
-                &format!("Payments:   {} ({} * 3)", self.payments * 3, self.payments),
+                &format!("Payments:   {} ({} * 3)", self.game_state.payments * 3, self.game_state.payments),
                 Color::RED,
                 Vector::new(10.0, 350.0),
             )?;
 
             font.draw( 
                 &mut gfx,
-                &format!("Trophies:   {} ({} * 2)", self.trophies * 2, self.trophies),
+                &format!("Trophies:   {} ({} * 2)", self.game_state.trophies * 2, self.game_state.trophies),
                 Color::RED,
                 Vector::new(10.0, 400.0),
             )?;
 
             font.draw( 
                 &mut gfx,
-                &format!("Cards left: {} (hand: {} deck: {})", self.hand.len() + self.deck.len(),
-                         self.hand.len(), self.deck.len()),
+                &format!("Cards left: {} (hand: {} deck: {})", self.game_state.hand.len() + self.game_state.deck.len(),
+                         self.game_state.hand.len(), self.game_state.deck.len()),
                 Color::RED,
                 Vector::new(10.0, 450.0),
             )?;
 
             font.draw( 
                 &mut gfx,
-                &format!("Total:      {}", self.payments * 3 
-                         + self.trophies * 2 
-                         + self.hand.len() as u32 + self.deck.len() as u32),
+                &format!("Total:      {}", self.game_state.score()),
                 Color::RED,
                 Vector::new(10.0, 500.0),
             )?;
 
+            font.draw( 
+                &mut gfx,
+                &format!("Seed:       {}", self.game_state.seed),
+                Color::RED,
+                Vector::new(10.0, 600.0),
+            )?;
+
+            font.draw( 
+                &mut gfx,
+                &format!("Difficulty: {}", self.game_state.difficulty),
+                Color::RED,
+                Vector::new(10.0, 650.0),
+            )?;
+
+            if self.ironman {
+                font.draw( 
+                    &mut gfx,
+                    "Ironman run",
+                    Color::RED,
+                    Vector::new(10.0, 700.0),
+                )?;
+            }
+
             return gfx.present(&window);
         }
 
         // Start row 1 from `PADDING` from the top
         let mut curr_y = PADDING;
 
+        // Preview the selected card on the action button under the pointer, found among the
+        // buttons of the last draw
+        let hovered = self.hovered_action().and_then(|action| self.preview(action));
+
         // Calculate the regions that are clickable from the drawing
         self.clickables.clear();
+        self.monster_regions.clear();
+
+        // Only show the buttons of actions that would do something
+        let available = self.available_actions();
+
+        // What the selected card would do for each action button, drawn over the board last
+        let mut previews = Vec::new();
 
         /* Row 1 */
         // Get the card type for the current player
-        let image = match self.player_kind {
+        let image = match self.game_state.player_kind {
             PlayerKind::Regular => &self.images[&AssetType::RegPlayer],
             PlayerKind::Monstrous =>     &self.images[&AssetType::MonstrousPlayer],
         };
 
         // Calculate the X coord based on the player index
         let image_width = image.size().x;
-        let curr_x = PADDING + (image_width + PADDING) * self.player_index as f32;
+        let curr_x = PADDING + (image_width + PADDING) * self.game_state.player_index as f32;
 
         // Draw the player image in Row 1
         let region = Rectangle::new(Vector::new(curr_x, curr_y), image.size());
@@ -495,70 +599,36 @@ impl Game {
         let reign_target_image = &self.images[&AssetType::ReignTarget];
         let reign_target_size = reign_target_image.size() * 0.2;
 
-        if self.player_index > 1 {
-            // Draw action clickables on the right side of the player
-            let region = Rectangle::new(
-                Vector::new(curr_x - range_target_size.x / 2.0, 
-                            curr_y + image.size().y * 0.20), 
-                range_target_size);
-            gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&range_target_image, region);
-
-            // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Range(Entity::Character, Direction::Left))));
-
-            // Draw action clickables on the right side of the player
-            let region = Rectangle::new(
-                Vector::new(curr_x - move_target_size.x / 2.0, 
-                            curr_y + image.size().y * 0.50), 
-                range_target_size);
-            gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&move_target_image, region);
-
-            // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Move(Entity::Character, Direction::Left))));
-        }
+        // Draw the buttons of every action the character can currently take
+        for (action, target_image, offset) in [
+            (Action::Range(Entity::Character, Direction::Left), range_target_image,
+                Vector::new(-range_target_size.x / 2.0, image.size().y * 0.20)),
+            (Action::Move(Entity::Character, Direction::Left), move_target_image,
+                Vector::new(-move_target_size.x / 2.0, image.size().y * 0.50)),
+            (Action::Range(Entity::Character, Direction::Right), range_target_image,
+                Vector::new(image_width - range_target_size.x / 2.0, image.size().y * 0.20)),
+            (Action::Move(Entity::Character, Direction::Right), move_target_image,
+                Vector::new(image_width - move_target_size.x / 2.0, image.size().y * 0.50)),
+            (Action::Melee(Entity::Character), melee_target_image,
+                Vector::new(image_width / 2.0 - melee_target_size.x / 2.0, 
+                            image.size().y - melee_target_size.y)),
+        ].iter() {
+            if !available.contains(action) {
+                continue;
+            }
 
-        if self.player_index < MONSTER_DECK_SIZE {
-            // Draw action clickables on the right side of the player
-            let region = Rectangle::new(
-                Vector::new(curr_x - range_target_size.x / 2.0 + image_width, 
-                            curr_y + image.size().y * 0.20), 
-                range_target_size);
+            let region = Rectangle::new(Vector::new(curr_x, curr_y) + *offset, target_image.size());
             gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&range_target_image, region);
+            gfx.draw_image(&target_image, region);
 
-            // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Range(Entity::Character, Direction::Right))));
-
-            // Draw action clickables on the right side of the player
-            let region = Rectangle::new(
-                Vector::new(curr_x - move_target_size.x / 2.0 + image_width, 
-                            curr_y + image.size().y * 0.50), 
-                move_target_size);
-            gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&move_target_image, region);
+            if let Some(label) = self.preview(*action).as_ref().and_then(preview_label) {
+                previews.push((region.pos, label));
+            }
 
             // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Move(Entity::Character, Direction::Right))));
+            self.clickables.push((region, ClickableType::Action(*action)));
         }
 
-        // Draw action clickables on the right side of the player
-        let region = Rectangle::new(
-            Vector::new(curr_x - melee_target_size.x / 2.0 + image_width / 2.0, 
-                        curr_y + image.size().y - melee_target_size.y), 
-            move_target_size);
-        gfx.stroke_rect(&region, Color::WHITE);
-        gfx.draw_image(&melee_target_image, region);
-
-        // Add this action to available clickables
-        self.clickables.push((region, 
-            ClickableType::Action(Action::Melee(Entity::Character))));
-
         /* End Row 1 */
 
         // Adjust the row to the second row
@@ -570,11 +640,11 @@ impl Game {
         /* Row 2 */
         let mut curr_x = PADDING;
         let mut monster_image_width = None;
-        for monster_index in 0..MONSTER_DECK_SIZE {
+        for monster_index in 0..self.game_state.dungeon_size() {
             // Draw quality of life indexes above monsters on character side to allow for easier 
             // count
-            let player_offset = (self.player_index as isize - monster_index as isize).abs();
-            if player_offset > 0 && player_offset <= 5 && monster_index > 0 {
+            let player_offset = (self.game_state.player_index as isize - monster_index as isize).abs();
+            if player_offset > 0 && player_offset <= 5 && monster_index != DECK_SLOT {
                 font.draw( 
                     &mut gfx,
                     &format!("{}", player_offset),
@@ -584,8 +654,8 @@ impl Game {
             }
 
             // Get the image of the monster based if it is alive or dead
-            let image = match self.monsters.alive[monster_index] {
-                true  => &self.monsters.images[monster_index],
+            let image = match self.game_state.monsters.alive[monster_index] {
+                true  => &self.monster_images[monster_index],
                 false => &self.images[&AssetType::CardBack]
             };
 
@@ -593,8 +663,8 @@ impl Game {
 
             // Draw quality of life indexes above monsters on character side to allow for easier 
             // count
-            let companion_offset = (self.companion_index as isize - monster_index as isize).abs();
-            if companion_offset > 0 && companion_offset <= 5 && monster_index > 0 {
+            let companion_offset = (self.game_state.companion_index as isize - monster_index as isize).abs();
+            if companion_offset > 0 && companion_offset <= 5 && monster_index != DECK_SLOT {
                 font.draw( 
                     &mut gfx,
                     &format!("{}", companion_offset),
@@ -610,9 +680,10 @@ impl Game {
             // Draw each image in Row 2
             let region = Rectangle::new(Vector::new(curr_x, curr_y), Vector::new(image_width, image_size.y));
             gfx.draw_image(&image, region);
+            self.monster_regions.push(region);
 
             // Draw each of the current hits on each monster
-            for (i, to_slay) in self.monsters.current_hits[monster_index].iter().enumerate() {
+            for (i, to_slay) in self.game_state.monsters.current_hits[monster_index].iter().enumerate() {
                 // Get the token image
                 let target_image = match to_slay {
                     ToSlay::Melee => melee_target_image,
@@ -632,9 +703,53 @@ impl Game {
                 gfx.stroke_rect(&region, Color::BLUE);
             }
 
+            // Highlight the monster affected by the hovered action along with the hit token it
+            // would receive, and whether it would die or earn a trophy
+            let affected = hovered.as_ref().filter(|outcome| outcome.target == Some(monster_index));
+            if let Some(outcome) = affected {
+                gfx.stroke_rect(&region, if outcome.killed { Color::RED } else { Color::YELLOW });
+
+                if let Some(to_slay) = outcome.hit {
+                    let target_image = match to_slay {
+                        ToSlay::Melee => melee_target_image,
+                        ToSlay::Range => range_target_image,
+                        ToSlay::Move  => move_target_image,
+                    };
+
+                    // Place the new token after the current hits
+                    let i = self.game_state.monsters.current_hits[monster_index].len();
+                    let token = Rectangle::new(
+                        Vector::new(curr_x + image.size().x * 0.5 - target_image.size().x * 0.5, 
+                                    curr_y + image.size().y * 0.2 + 
+                                        i as f32 * (PADDING + target_image.size().y)), 
+                                    target_image.size());
+
+                    gfx.draw_image(&target_image, token);
+                    gfx.stroke_rect(&token, Color::YELLOW);
+                }
+
+                let mut notes = Vec::new();
+                if outcome.killed {
+                    notes.push(("Dies", Color::RED));
+                }
+                if outcome.trophy {
+                    notes.push(("Trophy", Color::YELLOW));
+                }
+
+                for (line, (text, color)) in notes.iter().enumerate() {
+                    font.draw( 
+                        &mut gfx,
+                        text,
+                        *color,
+                        Vector::new(curr_x + 20.0, 
+                                    curr_y + image.size().y - 20.0 - line as f32 * 30.0),
+                    )?;
+                }
+            }
+
             // Draw the strength adjustment if it is there for each monster
-            if self.monsters.alive[monster_index] {
-                let adjustment = self.monsters.strength_adjustments[monster_index];
+            if self.game_state.monsters.alive[monster_index] {
+                let adjustment = self.game_state.monsters.strength_adjustments[monster_index];
                 if adjustment > 0 {
                     font.draw( 
                         &mut gfx,
@@ -645,14 +760,14 @@ impl Game {
                 }
             }
 
-            if matches!(self.monsters.abilities[monster_index], Some(Ability::Reign)) && 
-                    self.monsters.alive[monster_index] {
+            if matches!(self.game_state.monsters.abilities[monster_index], Some(Ability::Reign)) && 
+                    self.game_state.monsters.alive[monster_index] {
                 // Display Reign tooltip next to a monster that needs to be killed before the 
                 // current monster can be killed
-                if monster_index > 0 && self.monsters.alive[monster_index - 1] {
-                    let left_strength = self.monsters.strength(monster_index - 1);
-                    let curr_strength = self.monsters.strength(monster_index);
-                    if left_strength < curr_strength {
+                if monster_index > 0 && self.game_state.monsters.alive[monster_index - 1] {
+                    let left_strength = self.game_state.monsters.strength(monster_index - 1);
+                    let curr_strength = self.game_state.monsters.strength(monster_index);
+                    if matches!((left_strength, curr_strength), (Ok(left), Ok(curr)) if left < curr) {
                         let region = Rectangle::new(
                             Vector::new(curr_x, 
                                         curr_y + image.size().y * 0.5 - reign_target_size.y * 0.5), 
@@ -664,11 +779,11 @@ impl Game {
 
                 // Display Reign tooltip next to a monster that needs to be killed before the 
                 // current monster can be killed
-                if monster_index < (MONSTER_DECK_SIZE - 1) 
-                    && self.monsters.alive[monster_index + 1] {
-                    let right_strength = self.monsters.strength(monster_index + 1);
-                    let curr_strength = self.monsters.strength(monster_index);
-                    if right_strength < curr_strength {
+                if monster_index < (self.game_state.dungeon_size() - 1) 
+                    && self.game_state.monsters.alive[monster_index + 1] {
+                    let right_strength = self.game_state.monsters.strength(monster_index + 1);
+                    let curr_strength = self.game_state.monsters.strength(monster_index);
+                    if matches!((right_strength, curr_strength), (Ok(right), Ok(curr)) if right < curr) {
                         let region = Rectangle::new(
                             Vector::new(curr_x + image.size().x - reign_target_size.x, 
                                         curr_y + image.size().y * 0.5 - reign_target_size.y * 0.5), 
@@ -691,102 +806,52 @@ impl Game {
         curr_y += monster_image_width.unwrap().y + PADDING;
 
         /* Row 3 */
-        let image = match self.companion_kind {
+        let image = match self.game_state.companion_kind {
             CompanionKind::Melee => &self.images[&AssetType::MeleeCompanion],
             CompanionKind::Range => &self.images[&AssetType::RangeCompanion],
         };
 
         // Calculate the X coord based on the player index
         let image_width = image.size().x;
-        let curr_x = PADDING + (image_width + PADDING) * self.companion_index as f32;
+        let curr_x = PADDING + (image_width + PADDING) * self.game_state.companion_index as f32;
 
         // Draw the player image in Row 1
         let region = Rectangle::new(Vector::new(curr_x, curr_y), image.size());
         gfx.draw_image(&image, region);
 
-        if self.companion_index > 1 {
-            if matches!(self.companion_kind, CompanionKind::Range) {
-                // If the companion is range, draw the range action button on the left side
-                let region = Rectangle::new(
-                    Vector::new(curr_x - range_target_size.x / 2.0, 
-                                curr_y + image.size().y * 0.20), 
-                    range_target_size);
-                gfx.stroke_rect(&region, Color::WHITE);
-                gfx.draw_image(&range_target_image, region);
-
-                // Add this action to available clickables
-                self.clickables.push((region, 
-                    ClickableType::Action(Action::Range(Entity::Companion, Direction::Left))));
+        // Draw the buttons of every action the companion can currently take. Only a Range
+        // companion can shoot and only a Melee companion can fight.
+        for (action, target_image, offset) in [
+            (Action::Range(Entity::Companion, Direction::Left), range_target_image,
+                Vector::new(-range_target_size.x / 2.0, image.size().y * 0.20)),
+            (Action::Move(Entity::Companion, Direction::Left), move_target_image,
+                Vector::new(-move_target_size.x / 2.0, image.size().y * 0.50)),
+            (Action::Range(Entity::Companion, Direction::Right), range_target_image,
+                Vector::new(image_width - range_target_size.x / 2.0, image.size().y * 0.20)),
+            (Action::Move(Entity::Companion, Direction::Right), move_target_image,
+                Vector::new(image_width - move_target_size.x / 2.0, image.size().y * 0.50)),
+            (Action::Melee(Entity::Companion), melee_target_image,
+                Vector::new(image_width / 2.0 - melee_target_size.x / 2.0, 0.0)),
+            (Action::Swap, swap_target_image,
+                Vector::new(image_width / 2.0 - swap_target_size.x / 2.0, 
+                            image.size().y - swap_target_size.y)),
+        ].iter() {
+            if !available.contains(action) {
+                continue;
             }
 
-            // Draw the move action on the left of the companion
-            let region = Rectangle::new(
-                Vector::new(curr_x - move_target_size.x / 2.0, 
-                            curr_y + image.size().y * 0.50), 
-                range_target_size);
+            let region = Rectangle::new(Vector::new(curr_x, curr_y) + *offset, target_image.size());
             gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&move_target_image, region);
-
-            // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Move(Entity::Companion, Direction::Left))));
+            gfx.draw_image(&target_image, region);
 
-        }
-
-        if self.companion_index < MONSTER_DECK_SIZE {
-            // If the companion is range, draw the range action button on the right side
-            if matches!(self.companion_kind, CompanionKind::Range) {
-                let region = Rectangle::new(
-                    Vector::new(curr_x - range_target_size.x / 2.0 + image_width, 
-                                curr_y + image.size().y * 0.20), 
-                    range_target_size);
-                gfx.stroke_rect(&region, Color::WHITE);
-                gfx.draw_image(&range_target_image, region);
-
-                // Add this action to available clickables
-                self.clickables.push((region, 
-                    ClickableType::Action(Action::Range(Entity::Companion, Direction::Right))));
+            if let Some(label) = self.preview(*action).as_ref().and_then(preview_label) {
+                previews.push((region.pos, label));
             }
 
-            // Draw the move action on the right of the companion
-            let region = Rectangle::new(
-                Vector::new(curr_x - move_target_size.x / 2.0 + image_width, 
-                            curr_y + image.size().y * 0.50), 
-                move_target_size);
-            gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&move_target_image, region);
-
-            // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Move(Entity::Companion, Direction::Right))));
-        }
-
-
-        // If companion is melee, draw the melee action button
-        if matches!(self.companion_kind, CompanionKind::Melee) {
-            let region = Rectangle::new(
-                Vector::new(curr_x - melee_target_size.x / 2.0 + image_width / 2.0, 
-                            curr_y), 
-                move_target_size);
-            gfx.stroke_rect(&region, Color::WHITE);
-            gfx.draw_image(&melee_target_image, region);
-
             // Add this action to available clickables
-            self.clickables.push((region, 
-                ClickableType::Action(Action::Melee(Entity::Companion))));
+            self.clickables.push((region, ClickableType::Action(*action)));
         }
 
-        // Draw the swap action button
-        let region = Rectangle::new(
-            Vector::new(curr_x - swap_target_size.x / 2.0 + image_width / 2.0, 
-                        curr_y + image.size().y - swap_target_size.y), 
-            swap_target_size);
-        gfx.stroke_rect(&region, Color::WHITE);
-        gfx.draw_image(&swap_target_image, region);
-
-        // Add this action to available clickables
-        self.clickables.push((region, ClickableType::Action(Action::Swap)));
-
         /* End Row 3 */
 
         // Adjust the row to the fourth row
@@ -796,27 +861,38 @@ impl Game {
 
         let mut curr_x = PADDING;
 
-        // Alaways display the hand of cards in sorted order
-        self.hand.sort();
-
         let mut row_4_image_width = 0.0;
         // Draw the hand of cards
-        for (i, card) in self.hand.iter().enumerate() {
-            let image = &self.images[&AssetType::Action(*card)];
-            let image_size = image.size();
+        for (i, card) in self.game_state.hand.iter().enumerate() {
+            let image = self.images.get(&AssetType::Action(*card));
+            let image_size = match image {
+                Some(image) => image.size(),
+                None => self.images[&AssetType::CardBack].size()
+            };
             if row_4_image_width == 0.0 {
                 row_4_image_width = image_size.x;
             }
 
-            // Draw each action card
+            // Draw each action card, writing out the value of cards without an image
             let region = Rectangle::new(Vector::new(curr_x, curr_y), image_size);
-            gfx.draw_image(&image, region);
+            match image {
+                Some(image) => gfx.draw_image(&image, region),
+                None => {
+                    gfx.fill_rect(&region, Color::WHITE);
+                    font.draw( 
+                        &mut gfx,
+                        &format!("{}", card),
+                        Color::BLACK,
+                        Vector::new(curr_x + PADDING, curr_y + PADDING * 3.0),
+                    )?;
+                }
+            }
 
             // Add this card to available clickables
             self.clickables.push((region, ClickableType::Card(i)));
 
             // Update the column to the next column
-            curr_x += image.size().x + PADDING;
+            curr_x += image_size.x + PADDING;
         }
 
         let curr_x = PADDING  + (row_4_image_width + PADDING) * 6.0;
@@ -838,26 +914,217 @@ impl Game {
             Vector::new(curr_x + 3.0, curr_y + image.size().y / 4.0 - PADDING),
         )?;
 
+        // Draw the undo and redo buttons under the end turn button. Ironman runs only show
+        // that undo is disabled.
+        let button_y = curr_y + image.size().y * 0.30;
+        if self.ironman {
+            font.draw( 
+                &mut gfx,
+                "Ironman",
+                Color::RED,
+                Vector::new(curr_x + 3.0, button_y + image.size().y / 4.0 - PADDING),
+            )?;
+        } else {
+            let button_size = Vector::new((image.size().x - PADDING) / 2.0, image.size().y / 4.0);
+            for (i, (text, available, clickable)) in [
+                ("Undo", self.history.can_undo(), ClickableType::Undo),
+                ("Redo", self.history.can_redo(), ClickableType::Redo),
+            ].iter().enumerate() {
+                let button_x = curr_x + (button_size.x + PADDING) * i as f32;
+                let region = Rectangle::new(Vector::new(button_x, button_y), button_size);
+
+                // Grey out the button if there is nothing to undo or redo
+                if *available {
+                    gfx.fill_rect(&region, Color::WHITE);
+                } else {
+                    gfx.fill_rect(&region, Color::from_rgba(128, 128, 128, 1.0));
+                }
+                gfx.stroke_rect(&region, Color::GREEN);
+
+                // Add this button to available clickables
+                self.clickables.push((region, *clickable));
+
+                font.draw( 
+                    &mut gfx,
+                    text,
+                    Color::BLACK,
+                    Vector::new(button_x + 3.0, button_y + button_size.y - PADDING),
+                )?;
+            }
+        }
+
         let mut font = self.font.to_renderer(&gfx, 48.0)?;
         font.draw( 
             &mut gfx,
-            &format!("Deck left: {}", self.deck.len()),
+            &format!("Deck left: {}", self.game_state.deck.len()),
             Color::WHITE,
             Vector::new(curr_x + 3.0, curr_y + image.size().y * 0.75),
         )?;
 
         font.draw( 
             &mut gfx,
-            &format!("Trophies: {}", self.trophies),
+            &format!("Trophies: {}", self.game_state.trophies),
             Color::WHITE,
             Vector::new(curr_x + 3.0, curr_y + image.size().y * 1.0),
         )?;
 
+        font.draw( 
+            &mut gfx,
+            &format!("Seed: {}", self.game_state.seed),
+            Color::WHITE,
+            Vector::new(curr_x + 3.0, curr_y + image.size().y * 1.25),
+        )?;
+
+        font.draw( 
+            &mut gfx,
+            &format!("Difficulty: {}", self.game_state.difficulty),
+            Color::WHITE,
+            Vector::new(curr_x + 3.0, curr_y + image.size().y * 1.5),
+        )?;
+
+        // Draw the deck tracker to the right of the game information: the cards of each value
+        // not seen yet and the odds of drawing them
+        let tracker = DeckTracker::new(&self.game_state, &self.replay.rules);
+        let panel_x = curr_x + image.size().x * 2.5;
+        let mut panel_font = self.font.to_renderer(&gfx, 24.0)?;
+        let mut lines = vec![
+            match tracker.reshuffled {
+                true => format!("Reshuffled: {} known cards in deck", tracker.deck_size),
+                false => format!("Unseen: {} in deck, {} withheld", tracker.deck_size, tracker.withheld),
+            },
+            format!("Card Unseen  Next  Refill of {}", tracker.refill_size),
+        ];
+        for card in &tracker.cards {
+            lines.push(format!("{:>4} {:>6}  {:>3.0}%  {:>5.0}%", card.value, card.unseen,
+                               card.next_draw * 100.0, card.refill * 100.0));
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            panel_font.draw( 
+                &mut gfx,
+                line,
+                Color::WHITE,
+                Vector::new(panel_x, curr_y + 30.0 * (i + 1) as f32),
+            )?;
+        }
+
+        // Draw the button showing the discard pile under the deck tracker
+        let button_y = curr_y + 30.0 * lines.len() as f32 + PADDING;
+        let region = Rectangle::new(Vector::new(panel_x, button_y), Vector::new(300.0, 40.0));
+        gfx.fill_rect(&region, Color::WHITE);
+        gfx.stroke_rect(&region, Color::GREEN);
+        self.clickables.push((region, ClickableType::Discards));
+        panel_font.draw( 
+            &mut gfx,
+            &format!("Discards ({})", self.game_state.discard_pile.len()),
+            Color::BLACK,
+            Vector::new(panel_x + 3.0, button_y + 30.0),
+        )?;
+
+        // List the keyboard controls under the discard pile button
+        let legend_y = button_y + 40.0 + PADDING;
+        for (i, line) in [
+            "1-9: select card   Backspace: deselect",
+            "Q/W/E: character Move/Range/Melee",
+            "A/S/D: companion Move/Range/Melee",
+            "Left/Right: direction   X: swap",
+            "Enter: end turn   R: reset",
+            "Ctrl+Z/Ctrl+Y: undo/redo",
+        ].iter().enumerate() {
+            panel_font.draw( 
+                &mut gfx,
+                line,
+                Color::WHITE,
+                Vector::new(panel_x, legend_y + 30.0 * (i + 1) as f32),
+            )?;
+        }
+
+        // Ask for the direction of an action chosen from the keyboard
+        if let Some(directional) = self.directional {
+            panel_font.draw( 
+                &mut gfx,
+                &format!("{:?}: press Left or Right", directional),
+                Color::YELLOW,
+                Vector::new(panel_x, legend_y + 30.0 * 7.0),
+            )?;
+        }
+
+        // Ask for the card to lose once the player moved onto a Noxious monster
+        if self.game_state.discard_pending {
+            font.draw( 
+                &mut gfx,
+                "Noxious! Choose a card to discard",
+                Color::RED,
+                Vector::new(PADDING, curr_y + image.size().y * 1.25),
+            )?;
+        }
+
+        // Label the action buttons with what the selected card would do
+        let mut font = self.font.to_renderer(&gfx, 24.0)?;
+        for (position, label) in &previews {
+            font.draw(&mut gfx, label, Color::YELLOW, *position)?;
+        }
+
+        // List the discard pile over the board, most recent card first
+        if self.show_discards {
+            let lines: Vec<String> = self.game_state.discard_pile.iter().rev()
+                .take(MAX_DISCARDS_SHOWN)
+                .map(|discarded| self.describe_discard(discarded))
+                .collect();
+
+            let region = Rectangle::new(Vector::new(PADDING, PADDING),
+                                        Vector::new(700.0, 30.0 * (lines.len() + 1) as f32 + PADDING));
+            gfx.fill_rect(&region, Color::BLACK);
+            gfx.stroke_rect(&region, Color::WHITE);
+
+            font.draw(&mut gfx, &format!("Discard pile: {} cards", self.game_state.discard_pile.len()),
+                      Color::YELLOW, region.pos + Vector::new(PADDING, 30.0))?;
+            for (i, line) in lines.iter().enumerate() {
+                font.draw(&mut gfx, line, Color::WHITE,
+                          region.pos + Vector::new(PADDING, 30.0 * (i + 2) as f32))?;
+            }
+        }
+
+        // Describe the monster under the pointer on top of everything else
+        let location = self.pointer * (1.0 / self.scale);
+        let hovered_monster = self.monster_regions.iter()
+            .position(|region| region.contains(location))
+            .filter(|&index| index != DECK_SLOT);
+        if let Some(index) = hovered_monster {
+            let lines = self.monster_tooltip(index);
+            let size = Vector::new(700.0, 30.0 * lines.len() as f32 + PADDING);
+
+            // Keep the tooltip inside the window
+            let x = (location.x + PADDING).min(window.size().x / self.scale - size.x);
+            let region = Rectangle::new(Vector::new(x, location.y + PADDING), size);
+            gfx.fill_rect(&region, Color::BLACK);
+            gfx.stroke_rect(&region, Color::WHITE);
+
+            for (i, line) in lines.iter().enumerate() {
+                font.draw(&mut gfx, line, Color::WHITE,
+                          region.pos + Vector::new(PADDING, 30.0 * (i + 1) as f32))?;
+            }
+        }
+
+        // Explain why the last command was rejected
+        if let Some(message) = &self.message {
+            font.draw( 
+                &mut gfx,
+                message,
+                Color::RED,
+                Vector::new(PADDING, curr_y + image.size().y * 1.5),
+            )?;
+        }
 
         gfx.present(&window)
     }
 
     pub fn update(&mut self, location: Vector) {
+        let mut history_action = None;
+
+        // The clickables are in board coordinates, which are scaled to fit the window
+        let location = location * (1.0 / self.scale);
+
         for (region, new_action) in self.clickables.iter() {
             if region.contains(location) {
                 match new_action {
@@ -867,384 +1134,646 @@ impl Game {
                         self.state = State::Reset;
                         return;
                     }
+                    ClickableType::State(state @ State::HighScores)
+                        | ClickableType::State(state @ State::Statistics) => {
+                        self.state = *state;
+                        return;
+                    }
                     ClickableType::State(_) => {}
+                    ClickableType::Undo | ClickableType::Redo => history_action = Some(*new_action),
+                    ClickableType::Discards => {
+                        self.show_discards = !self.show_discards;
+                        return;
+                    }
                 }
             }
         }
 
-        // Variables set if an action is valid
-        let mut current_monster = None;
-        let mut reset = false;
-
-        // If we have selected a card and an action, perform the logic for that request
-        match (self.current_action, self.current_card) {
-            // Movement action
-            (Some(Action::Move(entity, direction)), Some(hand_index)) => {
-                assert!(hand_index < self.hand.len(), 
-                    "Move: Given hand_size {} larger than hand.len() {}", hand_index, 
-                    self.hand.len());
-                let num = self.hand.remove(hand_index) as usize;
-
-                let index = match (entity, direction) {
-                    (Entity::Character, Direction::Left) => {
-                        self.player_index = self.player_index.saturating_sub(num);
-                        if self.player_index == 0 {
-                            // Ensure we never move back onto the dungeon deck when moving left
-                            self.player_index = 1;
-                        }
-                        info!("New player index left: {}", self.player_index);
-                        self.player_index
-                    }
-                    (Entity::Character, Direction::Right) => {
-                        let mut new_index = self.player_index + num;
-                        if new_index >= MONSTER_DECK_SIZE {
-                            new_index = MONSTER_DECK_SIZE - 1;
-                        }
-                        self.player_index = new_index;
-                        info!("New player index right: {}", self.player_index);
-                        self.player_index
-                    }
-                    (Entity::Companion, Direction::Left) => {
-                        self.companion_index = self.companion_index.saturating_sub(num);
-                        if self.companion_index == 0 {
-                            // Ensure we never move back onto the dungeon deck when moving left
-                            self.companion_index = 1;
-                        }
-                        info!("New companion index: {}", self.companion_index);
-                        self.companion_index
-                    }
-                    (Entity::Companion, Direction::Right) => {
-                        let mut new_index = self.companion_index + num;
-                        if new_index >= MONSTER_DECK_SIZE {
-                            new_index = MONSTER_DECK_SIZE - 1;
-                        }
-
-                        self.companion_index = new_index;
-                        info!("New companion index: {}", self.companion_index);
-                        self.companion_index
-                    }
-                };
-
-                // Only add a Move hit if the current monster can be hit by Move
-                if self.monsters.to_slays[index].contains(&ToSlay::Move)  {
+        match history_action {
+            Some(ClickableType::Undo) => {
+                self.undo();
+                return;
+            }
+            Some(ClickableType::Redo) => {
+                self.redo();
+                return;
+            }
+            _ => {}
+        }
 
-                    // Add the ToSlay marker to the moved to monster
-                    self.monsters.current_hits[index].push(ToSlay::Move);
+        self.play_selection();
+    }
 
-                    // If the number used is the same as the strength, then add a trophy
-                    if num as u8 == self.monsters.strength(index) {
-                        self.trophies += 1;
-                    }
-                }
+    /// Handle a key pressed during the game: number keys select a card, letters choose an
+    /// action for the character or companion and the arrow keys give Moves and Range attacks
+    /// their direction
+    pub fn key(&mut self, key: Key) {
+        let card = match key {
+            Key::Key1 => Some(0),
+            Key::Key2 => Some(1),
+            Key::Key3 => Some(2),
+            Key::Key4 => Some(3),
+            Key::Key5 => Some(4),
+            Key::Key6 => Some(5),
+            Key::Key7 => Some(6),
+            Key::Key8 => Some(7),
+            Key::Key9 => Some(8),
+            _ => None
+        };
 
-                // Moving onto a Noxious monster results in randomly losing a card
-                if matches!(self.monsters.abilities[index], Some(Ability::Noxious)) 
-                        && self.monsters.alive[index] {
-                    if self.hand.len() > 0 {
-                        self.hand.remove(rand::random::<usize>() % self.hand.len());
-                        self.discarded = true;
-                    }
-                }
+        if let Some(card) = card {
+            if card < self.game_state.hand.len() {
+                self.current_card = Some(card);
+            }
+        }
 
-                current_monster = Some(index);
+        match key {
+            Key::Q => self.directional = Some(Directional::Move(Entity::Character)),
+            Key::W => self.directional = Some(Directional::Range(Entity::Character)),
+            Key::E => self.current_action = Some(Action::Melee(Entity::Character)),
+            Key::A => self.directional = Some(Directional::Move(Entity::Companion)),
+            Key::S => self.directional = Some(Directional::Range(Entity::Companion)),
+            Key::D => self.current_action = Some(Action::Melee(Entity::Companion)),
+            Key::X => self.current_action = Some(Action::Swap),
+            Key::Left | Key::Right => {
+                let direction = match key {
+                    Key::Left => Direction::Left,
+                    _ => Direction::Right
+                };
 
-                // Reset the chosen card and action
-                self.current_card   = None;
+                self.current_action = match self.directional.take() {
+                    Some(Directional::Move(entity)) => Some(Action::Move(entity, direction)),
+                    Some(Directional::Range(entity)) => Some(Action::Range(entity, direction)),
+                    None => self.current_action
+                };
+            }
+            Key::Return => self.current_action = Some(Action::EndTurn),
+            Key::Back => {
+                self.current_card = None;
                 self.current_action = None;
+                self.directional = None;
+                return;
             }
-            // Swap action
-            (Some(Action::Swap), Some(hand_index)) => {
-                // Ensure our hand_index is in bounds
-                assert!(hand_index < self.hand.len(), 
-                    "Swap: Given hand_size {} larger than hand.len() {}", 
-                    hand_index, self.hand.len());
-
-                // Remove the card from the hand
-                let _ = self.hand.remove(hand_index) as usize;
-
-                // Change the companion to the other kind
-                self.companion_kind = match self.companion_kind {
-                    CompanionKind::Melee => CompanionKind::Range,
-                    CompanionKind::Range => CompanionKind::Melee,
-                };
+            Key::R => {
+                self.state = State::Reset;
+                return;
+            }
+            _ => {}
+        }
 
-                // Reset the chosen card and action
-                self.current_card   = None;
-                self.current_action = None;
+        // Choosing a complete action replaces one still waiting for a direction
+        if matches!(key, Key::E | Key::D | Key::X | Key::Return) {
+            self.directional = None;
+        }
+
+        if matches!(self.state, State::Playing) {
+            self.play_selection();
+        }
+    }
+
+    /// If we have selected a card and an action, pass that command to the rules engine. While
+    /// a Noxious discard is pending, selecting a card is enough to discard it.
+    fn play_selection(&mut self) {
+        let command = match (self.current_action, self.current_card) {
+            (_, Some(hand_index)) if self.game_state.discard_pending =>
+                Command::Play(Action::Discard, hand_index),
+            (Some(Action::EndTurn), _) => Command::EndTurn,
+            (Some(action), Some(hand_index)) => Command::Play(action, hand_index),
+            _ => {
+                info!("Current actions: {:?} {:?}", self.current_action, self.current_card);
+                return;
             }
-            // Range action with Companion to the left
-            (Some(Action::Range(entity, Direction::Left)), Some(hand_index)) => {
-                // Ensure our hand_index is in bounds
-                assert!(hand_index < self.hand.len(), 
-                    "RangeLeft: Given hand_size {} larger than hand.len() {}", 
-                    hand_index, self.hand.len());
-
-                // Remove the card from the hand
-                let num = self.hand.remove(hand_index) as usize;
-
-                match entity {
-                    Entity::Companion => {
-                        // Ensure we are in bounds for the range attack
-                        if matches!(self.companion_index.checked_sub(num), Some(0..13)) {
-                            info!("Range LEFT Companion {} hitting {}", num, self.monsters.names[num]);
-
-                            current_monster = Some(self.companion_index - num);
-
-                            // Only add hits if the monster actually can be hit by Range
-                            if self.monsters.to_slays[self.companion_index - num].contains(&ToSlay::Range) {
-                                // Add the Range hit to the current hits
-                                self.monsters.current_hits[self.companion_index - num]
-                                    .push(ToSlay::Range);
-
-                                // Check if we should get a trophy
-                                let monster_str = self.monsters.strength(self.companion_index - num);
-                                if num as u8 == monster_str {
-                                    self.trophies += 1;
-                                }
-                            }
-                        }
-                    }
-                    Entity::Character => {
-                        // Ensure we are in bounds for the range attack
-                        if matches!(self.player_index.checked_sub(num), Some(0..13)) {
-                            info!("Range LEFT Character {} hitting {}", num, self.monsters.names[num]);
-
-                            current_monster = Some(self.player_index - num);
-
-                            // Only add hits if the monster actually can be hit by Range
-                            if self.monsters.to_slays[self.companion_index - num].contains(&ToSlay::Range) {
-                                // Add the Range hit to the current hits
-                                self.monsters.current_hits[self.player_index - num]
-                                    .push(ToSlay::Range);
-
-                                // Check if we should get a trophy
-                                let monster_str = self.monsters.strength(self.player_index - num);
-                                if num as u8 == monster_str {
-                                    self.trophies += 1;
-                                }
-                            }
+        };
+
+        // Reset the chosen card and action
+        self.current_card   = None;
+        self.current_action = None;
+
+        self.play(command);
+    }
+
+    /// Apply `command` to the game, recording it and saving the result
+    pub fn play(&mut self, command: Command) {
+        let turn = self.game_state.turn;
+        let snapshot = (self.game_state.clone(), self.replay.clone());
+        match self.game_state.apply(command) {
+            Ok(outcome) => {
+                info!("{:?}: {:?}", command, outcome);
+                self.message = None;
+                self.replay.record(turn, command, &outcome);
+
+                self.history.record(snapshot, &outcome);
+
+                if outcome.game_over {
+                    self.state = State::EndGame;
+                    self.save_replay();
+                    self.record_score();
+                    self.record_stats();
+
+                    // The run is over, so there is nothing left to continue
+                    if self.saving {
+                        if let Err(e) = SaveGame::delete(SAVE_FILE) {
+                            error!("Failed to delete {}: {}", SAVE_FILE, e);
                         }
                     }
+                } else {
+                    self.autosave();
                 }
-                // Reset the chosen card and action
-                self.current_card   = None;
-                self.current_action = None;
             }
-            // Range action with Companion to the Right
-            (Some(Action::Range(entity, Direction::Right)), Some(hand_index)) => {
-                // Ensure our hand_index is in bounds
-                assert!(hand_index < self.hand.len(), 
-                    "RangeRight: Given hand_size {} larger than hand.len() {}", 
-                    hand_index, self.hand.len());
-
-                // Remove the card from the hand
-                let num = self.hand.remove(hand_index) as usize;
-
-                match entity {
-                    Entity::Companion => {
-                        // Ensure companion are in bounds for the range attack
-                        if self.companion_index + num < MONSTER_DECK_SIZE {
-                            info!("Range RIGHT {} hitting {}", num, self.monsters.names[num]);
-                            current_monster = Some(self.companion_index + num);
-
-                            // Only add a Range hit if the monster can be hit by Range
-                            if self.monsters.to_slays[self.companion_index + num].contains(&ToSlay::Range) {
-                                // Add the Range hit
-                                self.monsters.current_hits[self.companion_index + num]
-                                    .push(ToSlay::Range);
-
-                                // Check if we should get a trophy
-                                let monster_str = self.monsters.strength(self.companion_index + num);
-                                if num as u8 == monster_str {
-                                    self.trophies += 1;
-                                }
-                            }
-                        }
-                    }
-                    Entity::Character => {
-                        // Ensure character are in bounds for the range attack
-                        if self.player_index + num < MONSTER_DECK_SIZE {
-                            info!("Range RIGHT {} hitting {}", num, self.monsters.names[num]);
-                            current_monster = Some(self.player_index + num);
-
-                            // Only add Range Hit if the current monster can be hit by Range
-                            if self.monsters.to_slays[self.companion_index + num].contains(&ToSlay::Range) {
-                                self.monsters.current_hits[self.player_index + num]
-                                    .push(ToSlay::Range);
-
-                                // Check if we should gain a trophy
-                                let monster_str = self.monsters.strength(self.player_index + num);
-                                if num as u8 == monster_str {
-                                    self.trophies += 1;
-                                }
-                            }
-                        }
-                    }
-                }
-
-                // Reset the chosen card and action
-                self.current_card   = None;
-                self.current_action = None;
+            Err(e) => {
+                error!("{:?}: {}\n{}", command, e, self.game_state);
+                self.message = Some(e.to_string());
             }
-            (Some(Action::Melee(entity)), Some(hand_index)) => {
-                // Ensure our hand_index is in bounds
-                assert!(hand_index < self.hand.len(), 
-                    "Melee: Given hand_size {} larger than hand.len() {}", 
-                    hand_index, self.hand.len());
-
-                // Remove the card from the hand
-                let num = self.hand.remove(hand_index);
-
-                // Get the monster index based on the entity using Melee
-                let monster_index = match entity {
-                    Entity::Character => self.player_index,
-                    Entity::Companion => self.companion_index,
-                };
+        }
+    }
+}
 
-                // Get the current monster strength
-                let monster_strength = self.monsters.strength(monster_index);
+impl Game {
+    /// Actions with a legal move for the selected card, or for any card in the hand if no card
+    /// is selected
+    fn available_actions(&self) -> Vec<Action> {
+        let selected = self.current_card.and_then(|index| self.game_state.hand.get(index));
+
+        legal_moves(&self.game_state).iter()
+            .filter(|legal| selected.is_none() || selected == Some(&legal.card))
+            .map(|legal| legal.action)
+            .collect()
+    }
 
-                // If the action card number is greater than or equal to the monster strength,
-                // it is a successful melee attack
-                if num >= monster_strength {
-                    current_monster = Some(monster_index);
+    /// Result of playing the selected card for `action`. `None` if no card is selected or the
+    /// play would be rejected.
+    fn preview(&self, action: Action) -> Option<Outcome> {
+        let hand_index = self.current_card?;
+        self.game_state.preview(Command::Play(action, hand_index)).ok()
+    }
 
-                    // Only add a Melee hit if the current monster can be hit by Melee
-                    if self.monsters.to_slays[monster_index].contains(&ToSlay::Melee) {
-                        // Add the Melee hit to the current monster
-                        self.monsters.current_hits[monster_index].push(ToSlay::Melee);
+    /// Lines of the tooltip describing the monster at `index`: its strength, the hits it still
+    /// needs and its ability
+    fn monster_tooltip(&self, index: usize) -> Vec<String> {
+        let monsters = &self.game_state.monsters;
+        let mut lines = vec![monsters.names[index].clone()];
 
-                        // Check if we should add a trophy
-                        if num == monster_strength {
-                            self.trophies += 1;
-                        }
-                    }
-                }
+        if !monsters.alive[index] {
+            lines.push("Slain".to_string());
+            return lines;
+        }
 
-                // Reset the chosen card and action
-                self.current_card   = None;
-                self.current_action = None;
-            }
-            (Some(Action::EndTurn), _) => {
-                reset = true;
+        let base = monsters.strengths[index];
+        let adjustment = monsters.strength_adjustments[index];
+        if adjustment > 0 {
+            lines.push(format!("Strength: {} (+{} Rally = {})", base, adjustment, base + adjustment));
+        } else {
+            lines.push(format!("Strength: {}", base));
+        }
 
-                // Reset the chosen card and action
-                self.current_card   = None;
-                self.current_action = None;
-            }
-            _ => { }
+        let remaining = monsters.remaining_hits(index);
+        if remaining.is_empty() {
+            lines.push("Needs: no more hits".to_string());
+        } else {
+            let hits: Vec<String> = remaining.iter().map(|hit| format!("{:?}", hit)).collect();
+            lines.push(format!("Needs: {}", hits.join(", ")));
         }
 
-        info!("Current actions: {:?} {:?}", self.current_action, self.current_card);
+        if let Some(ability) = monsters.abilities[index] {
+            lines.push(ability.description().to_string());
+        }
 
-        // Check if the current monster is dead by removing all elements from the 
-        // current hit Vector from the to_slay Vector. If at the end of that, the 
-        // to_slays vector is empty, then that monster is dead.
-        if let Some(index) = current_monster {
-            if self.monsters.alive[index] {
+        // Explain which neighbours currently keep a Reign monster alive
+        for blocker in monsters.reign_blockers(index) {
+            lines.push(format!("Protected until {} (strength {}) is slain",
+                               monsters.names[blocker],
+                               monsters.strengths[blocker] + monsters.strength_adjustments[blocker]));
+        }
 
-                let curr_hits = &self.monsters.current_hits[index];
-                let mut to_slays  = self.monsters.to_slays[index].clone();
+        lines
+    }
 
-                // Remove all current hits from the to_slays vec
-                for curr_hit in curr_hits {
-                    to_slays.remove_item(&curr_hit);
-                }
+    /// One line describing why the `discarded` card left the hand
+    fn describe_discard(&self, discarded: &DiscardedCard) -> String {
+        let names = &self.game_state.monsters.names;
+        let target = discarded.target.map(|index| names[index].as_str());
+
+        match (discarded.is_noxious_loss(), target) {
+            (true, Some(name)) => format!("Turn {}: {} lost to {}", discarded.turn, discarded.card, name),
+            (true, None) => format!("Turn {}: {} lost", discarded.turn, discarded.card),
+            (false, Some(name)) => format!("Turn {}: {} {:?} at {}", discarded.turn, discarded.card,
+                                           discarded.action, name),
+            (false, None) => format!("Turn {}: {} {:?}", discarded.turn, discarded.card,
+                                     discarded.action),
+        }
+    }
 
-                let mut reign = false;
-                let curr_strength = self.monsters.strength(index);
+    /// Action button under the pointer, among the buttons found by the last `draw()` call
+    fn hovered_action(&self) -> Option<Action> {
+        // The clickables are in board coordinates, which are scaled to fit the window
+        let location = self.pointer * (1.0 / self.scale);
 
-                if matches!(self.monsters.abilities[index], Some(Ability::Reign)) {
-                    if index > 0 {
-                        if self.monsters.alive[index - 1] {
-                            let left_strength = self.monsters.strength(index - 1);
-                            if left_strength >= curr_strength {
-                                reign = true;
-                            }
-                        }
-                    }
+        self.clickables.iter().find_map(|(region, clickable)| match clickable {
+            ClickableType::Action(action) if region.contains(location) => Some(*action),
+            _ => None
+        })
+    }
 
-                    if index < (MONSTER_DECK_SIZE - 1) {
-                        if self.monsters.alive[index + 1] {
-                            let right_strength = self.monsters.strength(index + 1);
-                            if right_strength >= curr_strength {
-                                reign = true;
-                            }
-                        }
-                    }
+    /// Take back the last card played this turn
+    fn undo(&mut self) {
+        if self.ironman {
+            return;
+        }
+
+        let mut current = (self.game_state.clone(), self.replay.clone());
+        if self.history.undo(&mut current) {
+            info!("Undo");
+            let (game_state, replay) = current;
+            self.game_state = game_state;
+            self.replay = replay;
+            self.current_action = None;
+            self.current_card = None;
+            self.autosave();
+        }
+    }
+
+    /// Play the last undone card again
+    fn redo(&mut self) {
+        if self.ironman {
+            return;
+        }
+
+        let mut current = (self.game_state.clone(), self.replay.clone());
+        if self.history.redo(&mut current) {
+            info!("Redo");
+            let (game_state, replay) = current;
+            self.game_state = game_state;
+            self.replay = replay;
+            self.current_action = None;
+            self.current_card = None;
+            self.autosave();
+        }
+    }
+
+    /// Write the current game to `SAVE_FILE` so it can be continued on the next launch
+    fn autosave(&self) {
+        if !self.saving {
+            return;
+        }
+
+        let save = SaveGame::new(self.game_state.clone(), self.replay.clone());
+        if let Err(e) = save.save(SAVE_FILE) {
+            error!("Failed to save {}: {}", SAVE_FILE, e);
+        }
+    }
+
+    /// Write the replay of the finished game to `REPLAY_DIR`
+    fn save_replay(&mut self) {
+        if !self.saving {
+            self.replay.finish(&self.game_state);
+            return;
+        }
+
+        match self.replay.save_finished(REPLAY_DIR, &self.game_state) {
+            Ok(path) => info!("Replay saved to {}", path.display()),
+            Err(e) => error!("Failed to save replay to {}: {}", REPLAY_DIR, e),
+        }
+    }
+
+    /// Add the `events` of the current game to the lifetime statistics in `STATS_FILE`
+    fn emit(&self, events: &[GameEvent]) {
+        if !self.saving || self.bot {
+            return;
+        }
+
+        if let Err(e) = LifetimeStats::accumulate(STATS_FILE, events) {
+            error!("Failed to update {}: {}", STATS_FILE, e);
+        }
+    }
+
+    /// Add every play of the finished game to the lifetime statistics at once. The plays are
+    /// taken from the replay, which no longer holds the plays that were undone.
+    fn record_stats(&self) {
+        if !self.saving || self.bot {
+            return;
+        }
+
+        if let Err(e) = LifetimeStats::accumulate_game(STATS_FILE, &self.replay) {
+            error!("Failed to update {}: {}\n{}", STATS_FILE, e, self.game_state);
+        }
+    }
+
+    /// Append the score of the finished game to `SCORES_FILE`. Games played by a bot are left
+    /// out of the player's scores.
+    fn record_score(&self) {
+        if !self.saving || self.bot {
+            return;
+        }
+
+        if let Err(e) = ScoreTable::record(SCORES_FILE, &self.game_state, self.ironman) {
+            error!("Failed to save score to {}: {}", SCORES_FILE, e);
+        }
+    }
+}
+
+/// Let the player choose the difficulty of a new game
+async fn difficulty_menu(window: &Window, gfx: &mut Graphics, input: &mut Input)
+        -> Result<Difficulty> {
+    let mut font = VectorFont::load("iosevka-regular.ttf").await?.to_renderer(&gfx, 72.0)?;
+    let regions: Vec<(Rectangle, Difficulty)> = DIFFICULTIES.iter().enumerate()
+        .map(|(i, difficulty)| {
+            let region = Rectangle::new(Vector::new(10.0, 100.0 + 100.0 * i as f32),
+                                        Vector::new(500.0, 80.0));
+            (region, *difficulty)
+        })
+        .collect();
+
+    loop {
+        while let Some(event) = input.next_event().await {
+            if let Event::PointerInput(e) = event {
+                if !e.is_down() {
+                    continue;
                 }
 
-                // If to_slays is empty, we have enough hits for the monster to be dead
-                if to_slays.len() == 0 && !reign {
-                    self.monsters.alive[index] = false;
-                    self.monsters.current_hits[index].clear();
+                let location = input.mouse().location();
+                for (region, difficulty) in &regions {
+                    if region.contains(location) {
+                        return Ok(*difficulty);
+                    }
                 }
             }
         }
 
-        // Reset all strength adjustments
-        for index in 0..MONSTER_DECK_SIZE {
-            self.monsters.strength_adjustments[index] = 0;
+        gfx.clear(Color::BLACK);
+        font.draw(gfx, "Difficulty", Color::WHITE, Vector::new(10.0, 70.0))?;
+
+        for (region, difficulty) in &regions {
+            gfx.stroke_rect(region, Color::GREEN);
+            font.draw(gfx, &difficulty.to_string(), Color::RED,
+                      Vector::new(region.pos.x + 10.0, region.pos.y + 65.0))?;
+        }
+
+        gfx.present(window)?;
+    }
+}
+
+/// List the best runs in `SCORES_FILE`, starting with the games played with the same rules as
+/// `game`. The buttons at the top cycle through the win/loss and difficulty filters.
+async fn high_score_menu(window: &Window, gfx: &mut Graphics, input: &mut Input,
+                         game: &GameState) -> Result<()> {
+    let font = VectorFont::load("iosevka-regular.ttf").await?;
+    let mut button_font = font.to_renderer(&gfx, 48.0)?;
+    let mut font = font.to_renderer(&gfx, 32.0)?;
+
+    let table = match ScoreTable::load(SCORES_FILE) {
+        Ok(table) => table,
+        Err(e) => {
+            error!("Failed to load {}: {}", SCORES_FILE, e);
+            ScoreTable::default()
         }
+    };
+
+    let same_rules = ScoreFilter::same_rules(game);
+    let mut filter = same_rules;
+    let outcome_region = Rectangle::new(Vector::new(10.0, 100.0), Vector::new(450.0, 70.0));
+    let rules_region = Rectangle::new(Vector::new(480.0, 100.0), Vector::new(450.0, 70.0));
+    let back_region = Rectangle::new(Vector::new(950.0, 100.0), Vector::new(200.0, 70.0));
+
+    loop {
+        while let Some(event) = input.next_event().await {
+            if let Event::PointerInput(e) = event {
+                if !e.is_down() {
+                    continue;
+                }
+
+                let location = input.mouse().location();
+                if outcome_region.contains(location) {
+                    filter.won = match filter.won {
+                        None => Some(true),
+                        Some(true) => Some(false),
+                        Some(false) => None,
+                    };
+                }
 
-        // Adjust the strength_adjustments for Rally monsters if that monster is alive
-        for index in 0..MONSTER_DECK_SIZE {
-            if matches!(self.monsters.abilities[index], Some(Ability::Rally)) 
-                    && self.monsters.alive[index] {
-                info!("Rally {}", index);
-                if index > 0 {
-                    self.monsters.strength_adjustments[index - 1] += 1;
+                // Cycle through every difficulty at the dungeon size and strictness of the game,
+                // followed by every game at once
+                if rules_region.contains(location) {
+                    filter.difficulty = match filter.difficulty {
+                        None => DIFFICULTIES.first().copied(),
+                        Some(current) => DIFFICULTIES.iter()
+                            .skip_while(|difficulty| **difficulty != current)
+                            .nth(1)
+                            .copied(),
+                    };
+
+                    let all = filter.difficulty.is_none();
+                    filter.dungeon_size = if all { None } else { same_rules.dungeon_size };
+                    filter.strict = if all { None } else { same_rules.strict };
                 }
 
-                if index < (MONSTER_DECK_SIZE - 1) {
-                    self.monsters.strength_adjustments[index + 1] += 1;
+                if back_region.contains(location) {
+                    return Ok(());
                 }
             }
         }
 
-        // We are out of cards in hand and should reset
-        if self.hand.len() == 0 && !self.discarded {
-            reset = true;
+        gfx.clear(Color::BLACK);
+        button_font.draw(gfx, "High scores", Color::WHITE, Vector::new(10.0, 70.0))?;
+
+        let outcome = match filter.won {
+            None => "Won and lost",
+            Some(true) => "Won only",
+            Some(false) => "Lost only",
+        };
+        let rules = match (filter.difficulty, filter.dungeon_size) {
+            (Some(difficulty), Some(size)) =>
+                format!("{} {}{}", difficulty, size, if game.strict { " strict" } else { "" }),
+            _ => "All games".to_string(),
+        };
 
-            // If we ran out of cards then we can always say the player is Monstrous
-            self.hand_limit = 6;
-            self.player_kind = PlayerKind::Monstrous;
+        for (region, text) in [(outcome_region, outcome), (rules_region, rules.as_str()),
+                               (back_region, "Back")].iter() {
+            gfx.stroke_rect(region, Color::GREEN);
+            button_font.draw(gfx, text, Color::RED,
+                             Vector::new(region.pos.x + 10.0, region.pos.y + 50.0))?;
+        }
+
+        let entries = table.top(&filter, MAX_HIGH_SCORES_SHOWN);
+        if entries.is_empty() {
+            font.draw(gfx, "No games played yet", Color::WHITE, Vector::new(10.0, 290.0))?;
+        }
 
+        font.draw(gfx, "    Score  Payments Trophies Cards  Result  Difficulty  Date        Seed",
+                  Color::YELLOW, Vector::new(10.0, 240.0))?;
+        for (i, entry) in entries.iter().enumerate() {
+            let line = format!("{:>2}. {:>5}  {:>8} {:>8} {:>5}  {:<6}  {:<10}  {}  {}{}",
+                               i + 1, entry.score, entry.payments * 3, entry.trophies * 2,
+                               entry.cards_left, if entry.won { "Won" } else { "Lost" },
+                               entry.difficulty, entry.date(), entry.seed,
+                               if entry.ironman { " (ironman)" } else { "" });
+            font.draw(gfx, &line, Color::WHITE, Vector::new(10.0, 290.0 + 45.0 * i as f32))?;
         }
 
-        if reset {
-            // Replinish cards up to hand limit
-            for _ in 0..(self.hand_limit - self.hand.len() as u8) {
-                if let Some(new_card) = self.deck.pop() {
-                    self.hand.push(new_card);
+        gfx.present(window)?;
+    }
+}
+
+/// Show the lifetime statistics in `STATS_FILE` until the player goes back
+async fn statistics_menu(window: &Window, gfx: &mut Graphics, input: &mut Input) -> Result<()> {
+    let font = VectorFont::load("iosevka-regular.ttf").await?;
+    let mut button_font = font.to_renderer(&gfx, 48.0)?;
+    let mut font = font.to_renderer(&gfx, 32.0)?;
+
+    let text = match LifetimeStats::load(STATS_FILE) {
+        Ok(stats) => stats.to_text(MAX_SURVIVORS_SHOWN),
+        Err(e) => {
+            error!("Failed to load {}: {}", STATS_FILE, e);
+            format!("Failed to load {}: {}", STATS_FILE, e)
+        }
+    };
+    let back_region = Rectangle::new(Vector::new(10.0, 100.0), Vector::new(200.0, 70.0));
+
+    loop {
+        while let Some(event) = input.next_event().await {
+            if let Event::PointerInput(e) = event {
+                if e.is_down() && back_region.contains(input.mouse().location()) {
+                    return Ok(());
                 }
             }
+        }
 
-            // Reset the current hits on all monsters
-            for curr_hit in self.monsters.current_hits.iter_mut() {
-                curr_hit.clear();
-            }
+        gfx.clear(Color::BLACK);
+        button_font.draw(gfx, "Statistics", Color::WHITE, Vector::new(10.0, 70.0))?;
 
+        gfx.stroke_rect(&back_region, Color::GREEN);
+        button_font.draw(gfx, "Back", Color::RED,
+                         Vector::new(back_region.pos.x + 10.0, back_region.pos.y + 50.0))?;
+
+        for (i, line) in text.lines().enumerate() {
+            font.draw(gfx, line, Color::WHITE, Vector::new(10.0, 240.0 + 40.0 * i as f32))?;
         }
 
-        // If all monsters are dead, game is over
-        if self.monsters.alive.iter().all(|&x| x == false) {
-            self.state = State::EndGame;
+        gfx.present(window)?;
+    }
+}
+
+/// Short description of a previewed play: the hit token added, a trophy earned or the monster
+/// killed. `None` if the play does none of these.
+fn preview_label(outcome: &Outcome) -> Option<String> {
+    let mut parts = Vec::new();
+    if let Some(hit) = outcome.hit {
+        parts.push(format!("{:?} hit", hit));
+    }
+    if outcome.trophy {
+        parts.push("Trophy".to_string());
+    }
+    if outcome.killed {
+        parts.push("Kill".to_string());
+    }
+
+    if parts.is_empty() {
+        return None;
+    }
+
+    Some(parts.join(", "))
+}
+
+/// Adjust the loaded `rules` to `difficulty`, overriding its dungeon size if one is given
+fn rules_for(rules: &Rules, difficulty: Difficulty, dungeon_size: Option<usize>)
+        -> std::result::Result<Rules, RulesError> {
+    rules.clone().with_difficulty(difficulty)
+        .and_then(|rules| match dungeon_size {
+            Some(size) => rules.with_dungeon_size(size),
+            None => Ok(rules)
+        })
+}
+
+/// Offer to continue the game saved by the last run. Returns `true` if the player chose to
+/// continue it rather than start a new game.
+async fn continue_menu(window: &Window, gfx: &mut Graphics, input: &mut Input) -> Result<bool> {
+    let mut font = VectorFont::load("iosevka-regular.ttf").await?.to_renderer(&gfx, 72.0)?;
+    let continue_region = Rectangle::new(Vector::new(10.0, 100.0), Vector::new(400.0, 80.0));
+    let new_game_region = Rectangle::new(Vector::new(10.0, 200.0), Vector::new(400.0, 80.0));
+
+    loop {
+        while let Some(event) = input.next_event().await {
+            if let Event::PointerInput(e) = event {
+                if !e.is_down() {
+                    continue;
+                }
+
+                let location = input.mouse().location();
+                if continue_region.contains(location) {
+                    return Ok(true);
+                }
+
+                if new_game_region.contains(location) {
+                    return Ok(false);
+                }
+            }
         }
 
-        // End game is triggered when no cards in hand and no cards left in the deck
-        if self.hand.len() == 0 && self.deck.len() == 0 {
-            self.state = State::EndGame;
+        gfx.clear(Color::BLACK);
+
+        for (region, text) in [(continue_region, "Continue"), (new_game_region, "New game")].iter() {
+            gfx.stroke_rect(region, Color::GREEN);
+            font.draw(gfx, text, Color::RED, Vector::new(region.pos.x + 10.0, region.pos.y + 65.0))?;
         }
 
-        self.discarded = false;
+        gfx.present(window)?;
     }
 }
 
-// This time we might return an error, so we use a Result
-async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()> {
+// This time we might return an error, so we use a Result. A verified `playback` replay is played
+// back as the first game, with its recorded commands as the player.
+async fn app(window: Window, mut gfx: Graphics, mut input: Input, args: Args,
+             mut playback: Option<Replay>) -> Result<()> {
+    let mut seed = args.seed;
+    let mut bot: Option<Box<dyn Strategy>> = match &args.bot {
+        Some(name) => match strategy::by_name(name, rand::random()) {
+            Some(bot) => Some(bot),
+            None => {
+                error!("Unknown strategy {}, expected one of {:?}", name, strategy::STRATEGIES);
+                None
+            }
+        },
+        None => None
+    };
+    let mut bot_timer = Timer::time_per_second(BOT_STEPS_PER_SECOND);
+
+    // Load the monster definitions and deck recipe, falling back to the built-in rules if they
+    // are invalid
+    let rules = match Rules::load(ASSET_DIR) {
+        Ok(rules) => rules,
+        Err(e) => {
+            error!("Failed to load rules: {}", e);
+            Rules::default()
+        }
+    };
+
+    // Offer to continue the game left unfinished by the last run
+    let mut saved = match SaveGame::load(SAVE_FILE) {
+        Ok(saved) => saved,
+        Err(e) => {
+            error!("Failed to load {}: {}", SAVE_FILE, e);
+            None
+        }
+    };
+
+    if playback.is_some() {
+        saved = None;
+    } else if saved.is_some() && !continue_menu(&window, &mut gfx, &mut input).await? {
+        saved = None;
+    }
+
     // Top of the reset loop. We will continue from 'reset_game when we get a reset game state
     'reset_game: loop {
+        gfx.set_transform(Transform::IDENTITY);
+
+        // Ask for the difficulty of a new game, unless it was given as an argument. A continued
+        // game keeps the difficulty it was started with.
+        let game_difficulty = match (&saved, args.difficulty) {
+            (Some(_), _) => Difficulty::default(),
+            (None, _) if playback.is_some() => Difficulty::default(),
+            (None, Some(difficulty)) => difficulty,
+            (None, None) => difficulty_menu(&window, &mut gfx, &mut input).await?,
+        };
+
         // Display the loading screen
         gfx.clear(Color::BLACK);
         let mut font = VectorFont::load("iosevka-regular.ttf").await?.to_renderer(&gfx, 72.0)?;
@@ -1252,18 +1781,38 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
         gfx.present(&window)?;
 
         // Initialize this game
-        let mut game = Game::init(&gfx).await?;
-
-        // Initial update
-        game.update(Vector::new(0.0, 0.0));
+        let mut script = None;
+        let mut game = match (playback.take(), saved.take()) {
+            (Some(replay), _) => {
+                let commands = replay.entries.iter().map(|entry| entry.command).collect();
+                script = Some(Scripted::new(commands));
+                let mut game = Game::init(&gfx, replay.seed, replay.ironman, &replay.rules).await?;
+                game.saving = false;
+                game
+            }
+            (None, Some(save)) => {
+                let mut game = Game::resume(&gfx, save.game_state, save.replay).await?;
+                game.bot = bot.is_some();
+                game
+            }
+            (None, None) => {
+                // Fall back to the loaded rules rather than refusing to start
+                let rules = args.rules(&rules, game_difficulty).unwrap_or_else(|e| {
+                    error!("Failed to apply {} difficulty: {}", game_difficulty, e);
+                    rules.clone()
+                });
+                let seed = seed.take().unwrap_or_else(rand::random);
+                let mut game = Game::init(&gfx, seed, args.ironman, &rules).await?;
+                game.bot = bot.is_some();
+                game.emit(&[GameEvent::started(&game.game_state)]);
+                game
+            }
+        };
 
         loop {
-            // let mut location = None;
             while let Some(event) = input.next_event().await {
                 match event {
-                    Event::PointerMoved(_e) => {
-                        // location = Some(e.location());
-                    }
+                    Event::PointerMoved(e) => game.pointer = e.location(),
                     Event::PointerInput(e) => {
                         if !e.is_down() {
                             continue;
@@ -1271,6 +1820,19 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
 
                         game.update(input.mouse().location());
                     }
+                    Event::KeyboardInput(e) => {
+                        if !e.is_down() {
+                            continue;
+                        }
+
+                        // Ctrl+Z undoes the last card played and Ctrl+Y plays it again
+                        let ctrl = input.key_down(Key::LControl) || input.key_down(Key::RControl);
+                        match e.key() {
+                            Key::Z if ctrl => game.undo(),
+                            Key::Y if ctrl => game.redo(),
+                            key => game.key(key),
+                        }
+                    }
                     _ => {
                         info!("Skipping.. {:?}", event);
                         continue;
@@ -1282,6 +1844,48 @@ async fn app(window: Window, mut gfx: Graphics, mut input: Input) -> Result<()>
                 continue 'reset_game;
             }
 
+            // The high-score and statistics screens take over the window until the player goes
+            // back
+            match game.state {
+                State::HighScores => {
+                    gfx.set_transform(Transform::IDENTITY);
+                    high_score_menu(&window, &mut gfx, &mut input, &game.game_state).await?;
+                    game.state = State::EndGame;
+                }
+                State::Statistics => {
+                    gfx.set_transform(Transform::IDENTITY);
+                    statistics_menu(&window, &mut gfx, &mut input).await?;
+                    game.state = State::EndGame;
+                }
+                _ => {}
+            }
+
+            // Let the replay or the bot play its next command, slowly enough to follow along
+            let player = match script.as_mut() {
+                Some(script) => Some(script as &mut dyn Strategy),
+                None => bot.as_deref_mut(),
+            };
+            let mut finished = false;
+            if let Some(bot) = player {
+                if matches!(game.state, State::Playing) && bot_timer.tick() {
+                    match bot.choose(&game.game_state) {
+                        Some(command) => {
+                            info!("{} plays {:?}", bot.name(), command);
+                            game.play(command);
+                        }
+                        None => {
+                            info!("{} has no commands left", bot.name());
+                            finished = true;
+                        }
+                    }
+                }
+            }
+
+            // Hand the game back to the player once the replay runs out
+            if finished {
+                script = None;
+            }
+
             gfx.clear(Color::BLACK);
 
             // Draw the current game state and populate the clickables to highlight in the UI
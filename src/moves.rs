@@ -0,0 +1,266 @@
+//! Enumeration of the card plays available in a game
+
+use crate::engine::*;
+
+/// A card from the hand played for an action
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Move {
+    /// Action the card is played for
+    pub action: Action,
+
+    /// Index of the card in the hand
+    pub hand_index: usize,
+
+    /// Value of the card
+    pub card: u8,
+}
+
+impl Move {
+    /// Get the `Command` that plays this move
+    pub fn command(&self) -> Command {
+        Command::Play(self.action, self.hand_index)
+    }
+}
+
+/// Every action a card can be played for
+const ACTIONS: [Action; 12] = [
+    Action::Move(Entity::Character, Direction::Left),
+    Action::Move(Entity::Character, Direction::Right),
+    Action::Move(Entity::Companion, Direction::Left),
+    Action::Move(Entity::Companion, Direction::Right),
+    Action::Range(Entity::Character, Direction::Left),
+    Action::Range(Entity::Character, Direction::Right),
+    Action::Range(Entity::Companion, Direction::Left),
+    Action::Range(Entity::Companion, Direction::Right),
+    Action::Melee(Entity::Character),
+    Action::Melee(Entity::Companion),
+    Action::Swap,
+    Action::Discard,
+];
+
+/// Enumerate every card play in the current position that actually does something. Cards of the
+/// same value give the same result, so only the first card of each value is used.
+///
+/// Ending the turn is always possible and is not included.
+pub fn legal_moves(game: &GameState) -> Vec<Move> {
+    let mut moves = Vec::new();
+
+    if game.is_over() {
+        return moves;
+    }
+
+    for (hand_index, &card) in game.hand.iter().enumerate() {
+        // Only the first card of each value is tried
+        if game.hand[..hand_index].contains(&card) {
+            continue;
+        }
+
+        for &action in ACTIONS.iter() {
+            if is_useful(game, action, card) {
+                moves.push(Move { action, hand_index, card });
+            }
+        }
+    }
+
+    moves
+}
+
+/// Returns `true` if playing `card` for `action` would change the game beyond spending the card
+pub fn is_useful(game: &GameState, action: Action, card: u8) -> bool {
+    // Nothing but the card lost to a Noxious monster can be chosen until it is discarded
+    if game.discard_pending {
+        return action == Action::Discard;
+    }
+
+    let num = card as usize;
+
+    // A 0 goes nowhere
+    if num == 0 && matches!(action, Action::Move(..) | Action::Range(..)) {
+        return false;
+    }
+
+    match action {
+        Action::Move(entity, Direction::Left) => {
+            // Moves to the left stop at the first monster, never the deck, so they only do
+            // something from the deck or beyond the first monster
+            game.position(entity) != 1
+        }
+        Action::Move(entity, Direction::Right) => {
+            // Moves to the right stop at the last monster
+            game.position(entity) < game.dungeon_size() - 1
+        }
+        Action::Range(entity, direction) => {
+            // Only a Range companion can shoot
+            if entity == Entity::Companion && game.companion_kind != CompanionKind::Range {
+                return false;
+            }
+
+            let index = game.position(entity);
+            let target = match direction {
+                Direction::Left => index.checked_sub(num),
+                Direction::Right => Some(index + num),
+            };
+
+            match target {
+                Some(target) => can_hit(game, target, ToSlay::Range),
+                None => false
+            }
+        }
+        Action::Melee(entity) => {
+            // Only a Melee companion can fight
+            if entity == Entity::Companion && game.companion_kind != CompanionKind::Melee {
+                return false;
+            }
+
+            let index = game.position(entity);
+            can_hit(game, index, ToSlay::Melee)
+                && matches!(game.monsters.strength(index), Ok(strength) if card >= strength)
+        }
+        Action::Swap => true,
+        Action::EndTurn | Action::Discard => false,
+    }
+}
+
+/// Returns `true` if `index` is a living monster that is slain with the help of `to_slay` hits
+fn can_hit(game: &GameState, index: usize, to_slay: ToSlay) -> bool {
+    if !game.monsters.monster_slots().contains(&index) {
+        return false;
+    }
+
+    game.monsters.alive[index] && game.monsters.to_slays[index].contains(&to_slay)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALL_HITS: [ToSlay; 3] = [ToSlay::Melee, ToSlay::Range, ToSlay::Move];
+    const LEFT: Direction = Direction::Left;
+    const RIGHT: Direction = Direction::Right;
+    const CHARACTER: Entity = Entity::Character;
+
+    /// Game with a dungeon row of `monsters` living monsters of the given strength, each needing
+    /// every kind of hit, and the character at `player_index` holding `hand`
+    fn game(monsters: usize, strength: u8, player_index: usize, hand: &[u8]) -> GameState {
+        let mut game = GameState::new(1);
+        let slots = monsters + 1;
+
+        game.monsters = Monsters {
+            names: (0..slots).map(|index| format!("Monster {}", index)).collect(),
+            strengths: vec![strength; slots],
+            strength_adjustments: vec![0; slots],
+            abilities: vec![None; slots],
+            to_slays: vec![ALL_HITS.to_vec(); slots],
+            current_hits: vec![Vec::new(); slots],
+            alive: vec![true; slots],
+            images: vec![String::new(); slots],
+        };
+
+        game.monsters.alive[DECK_SLOT] = false;
+        game.monsters.to_slays[DECK_SLOT].clear();
+
+        game.player_index = player_index;
+        game.companion_index = player_index;
+        game.companion_kind = CompanionKind::Melee;
+        game.hand = hand.to_vec();
+        game.discard_pending = false;
+        game
+    }
+
+    #[test]
+    fn range_reaches_first_and_last_slot() {
+        let from_first = game(4, 1, 1, &[3]);
+        assert!(is_useful(&from_first, Action::Range(CHARACTER, RIGHT), 3));
+        assert!(!is_useful(&from_first, Action::Range(CHARACTER, RIGHT), 4));
+
+        let from_last = game(4, 1, 4, &[3]);
+        assert!(is_useful(&from_last, Action::Range(CHARACTER, LEFT), 3));
+        assert!(!is_useful(&from_last, Action::Range(CHARACTER, RIGHT), 1));
+    }
+
+    #[test]
+    fn melee_on_first_and_last_slot() {
+        for &index in &[1, 4] {
+            let game = game(4, 3, index, &[2, 3]);
+            assert!(is_useful(&game, Action::Melee(CHARACTER), 3));
+            assert!(!is_useful(&game, Action::Melee(CHARACTER), 2));
+        }
+    }
+
+    #[test]
+    fn moves_stop_at_the_edges() {
+        // Moving left from the deck lands on the first monster
+        let deck = game(4, 1, 0, &[2]);
+        assert!(is_useful(&deck, Action::Move(CHARACTER, LEFT), 2));
+        assert!(is_useful(&deck, Action::Move(CHARACTER, RIGHT), 2));
+        assert!(legal_moves(&deck).iter().any(|legal| legal.action == Action::Move(CHARACTER, LEFT)));
+
+        let first = game(4, 1, 1, &[1]);
+        assert!(!is_useful(&first, Action::Move(CHARACTER, LEFT), 1));
+        assert!(is_useful(&first, Action::Move(CHARACTER, RIGHT), 1));
+
+        let last = game(4, 1, 4, &[1]);
+        assert!(is_useful(&last, Action::Move(CHARACTER, LEFT), 1));
+        assert!(!is_useful(&last, Action::Move(CHARACTER, RIGHT), 1));
+    }
+
+    #[test]
+    fn deck_slot_is_never_a_target() {
+        // Even a deck slot marked alive and needing every hit is never targeted
+        let mut game = game(4, 0, 2, &[2]);
+        game.monsters.alive[DECK_SLOT] = true;
+        game.monsters.to_slays[DECK_SLOT] = ALL_HITS.to_vec();
+        assert!(!is_useful(&game, Action::Range(CHARACTER, LEFT), 2));
+
+        game.player_index = 0;
+        game.companion_index = 0;
+        assert!(!is_useful(&game, Action::Melee(CHARACTER), 2));
+        assert!(!is_useful(&game, Action::Melee(Entity::Companion), 2));
+        assert!(legal_moves(&game).iter().all(|legal| {
+            !matches!(legal.action, Action::Melee(_) | Action::Range(_, LEFT))
+        }));
+    }
+
+    #[test]
+    fn zero_only_fights_and_swaps() {
+        let game = game(4, 0, 2, &[0]);
+        let moves = legal_moves(&game);
+        assert!(moves.iter().all(|legal| matches!(legal.action, Action::Melee(_) | Action::Swap)));
+        assert!(moves.iter().any(|legal| legal.action == Action::Melee(CHARACTER)));
+    }
+
+    #[test]
+    fn dead_monsters_are_excluded() {
+        let mut game = game(4, 1, 2, &[1]);
+        game.monsters.alive[2] = false;
+        game.monsters.alive[3] = false;
+
+        assert!(!is_useful(&game, Action::Melee(CHARACTER), 1));
+        assert!(!is_useful(&game, Action::Range(CHARACTER, RIGHT), 1));
+        assert!(is_useful(&game, Action::Range(CHARACTER, LEFT), 1));
+    }
+
+    #[test]
+    fn only_discard_while_discard_is_pending() {
+        let mut game = game(4, 1, 2, &[1, 2, 2, 5]);
+        game.discard_pending = true;
+
+        let moves = legal_moves(&game);
+        assert!(moves.iter().all(|legal| legal.action == Action::Discard));
+        assert_eq!(moves.iter().map(|legal| legal.card).collect::<Vec<_>>(), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn equal_cards_give_a_single_move() {
+        let game = game(4, 1, 2, &[2, 2, 2, 3]);
+        let moves = legal_moves(&game);
+
+        for (i, a) in moves.iter().enumerate() {
+            assert!(moves[i + 1..].iter().all(|b| (a.action, a.card) != (b.action, b.card)));
+            assert_eq!(game.hand[a.hand_index], a.card);
+        }
+
+        assert!(moves.iter().filter(|legal| legal.card == 2).all(|legal| legal.hand_index == 0));
+        assert!(moves.iter().any(|legal| legal.card == 3));
+    }
+}
@@ -0,0 +1,324 @@
+//! Action log of a single game and the versioned replay file format used to store it.
+//!
+//! A replay holds the seed of the game along with every `Command` applied to it. Since every
+//! random decision is drawn from the seed, re-applying the commands to a new game with the same
+//! seed must reach the exact same final score.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::*;
+use crate::rules::{Difficulty, Rules};
+use crate::scores::timestamp;
+
+/// Current version of the replay file format
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A single command applied during the game
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReplayEntry {
+    /// Turn the command was applied in
+    pub turn: u32,
+
+    /// Command given to the rules engine
+    pub command: Command,
+
+    /// Value of the card played by the command
+    pub card: Option<u8>,
+
+    /// Card lost to a Noxious monster as a result of the command
+    pub noxious_discard: Option<u8>,
+}
+
+/// Final score of a game, as shown on the EndGame screen
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FinalScore {
+    /// Every monster was slain
+    pub won: bool,
+
+    /// Cards withheld at the beginning of the game
+    pub payments: u32,
+
+    /// Trophies gathered during the game
+    pub trophies: u32,
+
+    /// Cards left in the hand and deck
+    pub cards_left: u32,
+
+    /// Total score
+    pub total: u32,
+
+    /// Difficulty the game was played at
+    pub difficulty: Difficulty,
+}
+
+impl FinalScore {
+    /// Get the score of the given game
+    pub fn of(game: &GameState) -> FinalScore {
+        FinalScore {
+            won: game.is_won(),
+            payments: game.payments,
+            trophies: game.trophies,
+            cards_left: (game.hand.len() + game.deck.len()) as u32,
+            total: game.score(),
+            difficulty: game.difficulty,
+        }
+    }
+}
+
+/// Errors found while reading or verifying a replay
+#[derive(Debug)]
+pub enum ReplayError {
+    /// Failed to read or write the replay file
+    Io(io::Error),
+
+    /// Replay file is not valid
+    Parse(serde_json::Error),
+
+    /// Replay file was written by an unknown version of the format
+    UnsupportedVersion(u32),
+
+    /// The recorded rules cannot create a game
+    Setup(RuleError),
+
+    /// A recorded command was rejected by the rules engine
+    Rule { index: usize, error: RuleError },
+
+    /// A recorded command did not produce the recorded result
+    Mismatch { index: usize, expected: ReplayEntry, found: ReplayEntry },
+
+    /// The recorded commands stop before the game is over
+    Unfinished,
+
+    /// The replayed game did not reach the recorded final score
+    ScoreMismatch { expected: Option<FinalScore>, found: FinalScore },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(e) => write!(f, "Replay IO error: {}", e),
+            ReplayError::Parse(e) => write!(f, "Invalid replay: {}", e),
+            ReplayError::UnsupportedVersion(version) =>
+                write!(f, "Unsupported replay version {} (expected {})", version, REPLAY_VERSION),
+            ReplayError::Setup(e) => write!(f, "Cannot start the replayed game: {}", e),
+            ReplayError::Rule { index, error } =>
+                write!(f, "Command {} was rejected: {}", index, error),
+            ReplayError::Mismatch { index, expected, found } =>
+                write!(f, "Command {} diverged: expected {:?} found {:?}", index, expected, found),
+            ReplayError::Unfinished => write!(f, "The replay stops before the game is over"),
+            ReplayError::ScoreMismatch { expected, found } =>
+                write!(f, "Final score diverged: expected {:?} found {:?}", expected, found),
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(e: io::Error) -> ReplayError {
+        ReplayError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(e: serde_json::Error) -> ReplayError {
+        ReplayError::Parse(e)
+    }
+}
+
+/// Recorded game that can be saved, loaded and re-executed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replay {
+    /// Version of the replay format
+    pub version: u32,
+
+    /// Seed the game was created from
+    pub seed: u64,
+
+    /// Game was played without undo
+    pub ironman: bool,
+
+    /// Rules the game was created with
+    pub rules: Rules,
+
+    /// Every command applied to the game, in order
+    pub entries: Vec<ReplayEntry>,
+
+    /// Final score of the game, once it is over
+    pub score: Option<FinalScore>,
+}
+
+impl Replay {
+    /// Start recording a new game created from `seed`
+    pub fn new(seed: u64) -> Replay {
+        Replay {
+            version: REPLAY_VERSION,
+            seed,
+            ironman: false,
+            rules: Rules::default(),
+            entries: Vec::new(),
+            score: None,
+        }
+    }
+
+    /// Record a command that was applied in `turn` along with its `outcome`
+    pub fn record(&mut self, turn: u32, command: Command, outcome: &Outcome) {
+        self.entries.push(ReplayEntry {
+            turn,
+            command,
+            card: outcome.card,
+            noxious_discard: outcome.noxious_discard,
+        });
+    }
+
+    /// Record the final score of the given finished game
+    pub fn finish(&mut self, game: &GameState) {
+        self.score = Some(FinalScore::of(game));
+    }
+
+    /// Write this replay to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        if let Some(parent) = path.as_ref().parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Record the final score of the finished `game` and write this replay to a new file in
+    /// `dir`, named after the seed and the current time. Returns the path of the file.
+    pub fn save_finished(&mut self, dir: impl AsRef<Path>, game: &GameState)
+            -> Result<PathBuf, ReplayError> {
+        self.finish(game);
+
+        let path = dir.as_ref().join(format!("{}-{}.json", game.seed, timestamp()));
+        self.save(&path)?;
+        Ok(path)
+    }
+
+    /// Read a replay from `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Replay, ReplayError> {
+        let replay: Replay = serde_json::from_str(&fs::read_to_string(path)?)?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(ReplayError::UnsupportedVersion(replay.version));
+        }
+
+        Ok(replay)
+    }
+
+    /// Re-execute every command against a new game from the same seed and rules, checking that each
+    /// command reproduces its recorded result and that the game ends with the recorded score.
+    /// Returns the replayed game.
+    pub fn verify(&self) -> Result<GameState, ReplayError> {
+        let mut game = GameState::with_rules(self.seed, &self.rules)
+            .map_err(ReplayError::Setup)?;
+
+        for (index, expected) in self.entries.iter().enumerate() {
+            let turn = game.turn;
+            let outcome = game.apply(expected.command)
+                .map_err(|error| ReplayError::Rule { index, error })?;
+
+            let found = ReplayEntry {
+                turn,
+                command: expected.command,
+                card: outcome.card,
+                noxious_discard: outcome.noxious_discard,
+            };
+
+            if found != *expected {
+                return Err(ReplayError::Mismatch { index, expected: *expected, found });
+            }
+        }
+
+        if !game.is_over() {
+            return Err(ReplayError::Unfinished);
+        }
+
+        let found = FinalScore::of(&game);
+        if self.score != Some(found) {
+            return Err(ReplayError::ScoreMismatch { expected: self.score, found });
+        }
+
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{play_out, Greedy};
+
+    /// Replay of a whole game played by the greedy strategy
+    fn played(seed: u64) -> Replay {
+        let mut game = GameState::new(seed);
+        let mut replay = Replay::new(seed);
+        play_out(&mut game, &mut Greedy, &mut replay).expect("Greedy played an invalid command");
+        replay
+    }
+
+    #[test]
+    fn round_trip_verifies() {
+        let replay = played(7);
+        assert!(!replay.entries.is_empty());
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let loaded: Replay = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, replay);
+
+        let game = loaded.verify().expect("Replay did not verify");
+        assert_eq!(Some(FinalScore::of(&game)), replay.score);
+    }
+
+    #[test]
+    fn changed_command_is_rejected() {
+        let mut replay = played(7);
+
+        // Ending the turn instead of playing a card cannot reproduce the recorded card
+        let index = replay.entries.iter()
+            .position(|entry| matches!(entry.command, Command::Play(..)))
+            .unwrap();
+        replay.entries[index].command = Command::EndTurn;
+
+        let json = serde_json::to_string(&replay).unwrap();
+        let loaded: Replay = serde_json::from_str(&json).unwrap();
+        assert!(loaded.verify().is_err());
+    }
+
+    #[test]
+    fn unfinished_game_is_rejected() {
+        let mut replay = played(7);
+        replay.entries.pop();
+        assert!(matches!(replay.verify(), Err(ReplayError::Unfinished)));
+    }
+
+    #[test]
+    fn finished_replay_is_saved_with_its_score() {
+        let dir = std::env::temp_dir().join(format!("maverick-replays-{}", std::process::id()));
+        let mut game = GameState::new(7);
+        let mut replay = Replay::new(7);
+        play_out(&mut game, &mut Greedy, &mut replay).unwrap();
+        replay.score = None;
+
+        let path = replay.save_finished(&dir, &game).unwrap();
+        let loaded = Replay::load(&path).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(path.file_name().unwrap().to_string_lossy().starts_with("7-"));
+        assert_eq!(loaded.score, Some(FinalScore::of(&game)));
+        assert_eq!(loaded, replay);
+    }
+
+    #[test]
+    fn changed_seed_is_rejected() {
+        let mut replay = played(7);
+        replay.seed = 8;
+        assert!(replay.verify().is_err());
+    }
+}
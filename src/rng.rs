@@ -0,0 +1,72 @@
+//! Seeded random number generator owned by each game
+
+use rand::{RngCore, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+/// Random number generator of a game. Serialized as its seed and position in the stream so a
+/// restored game makes the exact same random decisions as the original.
+#[derive(Debug, Clone)]
+pub struct GameRng {
+    /// Seed the generator was created from
+    seed: u64,
+
+    /// Underlying generator
+    rng: ChaCha8Rng,
+}
+
+impl GameRng {
+    /// Create a generator from the given `seed`
+    pub fn new(seed: u64) -> GameRng {
+        GameRng {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+
+    /// Position of the generator in its stream, in 32-bit words
+    pub fn position(&self) -> u128 {
+        self.rng.get_word_pos()
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+/// Serialized form of a `GameRng`
+#[derive(Serialize, Deserialize)]
+struct RngState {
+    seed: u64,
+    position: u128,
+}
+
+impl Serialize for GameRng {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RngState { seed: self.seed, position: self.position() }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for GameRng {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<GameRng, D::Error> {
+        let state = RngState::deserialize(deserializer)?;
+
+        let mut rng = GameRng::new(state.seed);
+        rng.rng.set_word_pos(state.position);
+        Ok(rng)
+    }
+}
@@ -0,0 +1,726 @@
+//! Settings a game is created from, such as the monsters that can appear in the dungeon and the
+//! cards in the action deck, along with the difficulty presets that adjust them.
+//!
+//! Monster definitions and the deck recipe are read from JSON files so they can be changed
+//! without recompiling:
+//!
+//! ```json
+//! [
+//!     { "name": "Dragon", "strength": 5, "ability": "Reign", "to_slay": ["Move", "Melee", "Range"],
+//!       "image": "monsters_small/Dragon.png" }
+//! ]
+//! ```
+//!
+//! ```json
+//! [
+//!     { "value": 1, "count": 8, "image": "actions_small/1black.png" },
+//!     { "value": 6, "count": 2 }
+//! ]
+//! ```
+//!
+//! Every problem found in a file is reported along with the line of the offending entry.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::consts::*;
+use crate::engine::*;
+
+/// Monster definitions used when no other file is given
+const DEFAULT_MONSTERS: &str = include_str!("../static/monsters.json");
+
+/// Deck recipe used when no other file is given
+const DEFAULT_DECK: &str = include_str!("../static/deck.json");
+
+/// Highest value an action card can have
+pub const MAX_CARD_VALUE: u8 = 6;
+
+/// Smallest dungeon row: the deck and a single monster
+pub const MIN_DUNGEON_SIZE: usize = 2;
+
+/// Highest base strength a monster can have. A monster between two Rally monsters is 2 stronger,
+/// which must still fit in a `u8`.
+pub const MAX_MONSTER_STRENGTH: u8 = u8::MAX - 2;
+
+/// Everything a monster brings into the dungeon
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MonsterDef {
+    /// Name of the monster, unique among the definitions
+    pub name: String,
+
+    /// Base strength of the monster
+    pub strength: u8,
+
+    /// Special ability of the monster, if any
+    pub ability: Option<Ability>,
+
+    /// Hits needed in a single turn to slay the monster
+    pub to_slay: Vec<ToSlay>,
+
+    /// Path of the monster's image, relative to the asset directory
+    pub image: String,
+}
+
+/// A monster exactly as written in the definitions file, before it is validated
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawMonsterDef {
+    name: String,
+    strength: u8,
+    ability: Option<String>,
+    to_slay: Vec<String>,
+    image: String,
+}
+
+/// Copies of a single card value in the action deck
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CardCount {
+    /// Value of the card
+    pub value: u8,
+
+    /// Number of copies in the deck
+    pub count: u32,
+
+    /// Path of the card's image, relative to the asset directory. Cards without an image are
+    /// drawn as their value.
+    #[serde(default)]
+    pub image: Option<String>,
+}
+
+/// Settings that do not fit together, so no game can be created from them
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The dungeon cannot have `size` slots. Every slot but the deck needs a different monster,
+    /// so the largest dungeon has one slot more than there are monsters.
+    DungeonSize { size: usize, min: usize, max: usize },
+
+    /// There are not enough cards to pay the payments and deal the starting hand
+    TooFewCards { found: u32, needed: u32 },
+
+    /// The starting hand has no cards, so no card could ever be played
+    EmptyHand,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::DungeonSize { size, min, max } =>
+                write!(f, "Dungeon size {} must be between {} and {}", size, min, max),
+            ConfigError::TooFewCards { found, needed } =>
+                write!(f, "Only {} cards in the deck, the game needs at least {}", found, needed),
+            ConfigError::EmptyHand => write!(f, "The starting hand must hold at least one card"),
+        }
+    }
+}
+
+/// Problems with a single entry of a definitions file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefinitionProblem {
+    /// The ability is not one of Noxious, Rally or Reign
+    UnknownAbility(String),
+
+    /// A to-slay hit is not one of Melee, Range or Move
+    UnknownToSlay(String),
+
+    /// The monster cannot be slain because it needs no hits
+    NoToSlay,
+
+    /// The monster on `first_line` already has this name
+    DuplicateName { name: String, first_line: usize },
+
+    /// The strength is higher than `MAX_MONSTER_STRENGTH`
+    InvalidStrength(u8),
+
+    /// The image does not exist in the asset directory
+    MissingImage(String),
+
+    /// The card value is higher than `MAX_CARD_VALUE`
+    InvalidCardValue(u8),
+
+    /// The card on `first_line` already has this value
+    DuplicateCardValue { value: u8, first_line: usize },
+}
+
+/// A problem found in the definitions file along with the line it was found on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DefinitionError {
+    /// Line of the offending monster, starting at 1
+    pub line: usize,
+
+    pub problem: DefinitionProblem,
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+
+        match &self.problem {
+            DefinitionProblem::UnknownAbility(ability) =>
+                write!(f, "Unknown ability {:?}, expected Noxious, Rally or Reign", ability),
+            DefinitionProblem::UnknownToSlay(to_slay) =>
+                write!(f, "Unknown to-slay {:?}, expected Melee, Range or Move", to_slay),
+            DefinitionProblem::NoToSlay => write!(f, "Monster has nothing to slay it with"),
+            DefinitionProblem::DuplicateName { name, first_line } =>
+                write!(f, "Duplicate monster {}, first defined on line {}", name, first_line),
+            DefinitionProblem::InvalidStrength(strength) =>
+                write!(f, "Strength {} is higher than {}", strength, MAX_MONSTER_STRENGTH),
+            DefinitionProblem::MissingImage(image) => write!(f, "Missing image {}", image),
+            DefinitionProblem::InvalidCardValue(value) =>
+                write!(f, "Card value {} is higher than {}", value, MAX_CARD_VALUE),
+            DefinitionProblem::DuplicateCardValue { value, first_line } =>
+                write!(f, "Duplicate card value {}, first defined on line {}", value, first_line),
+        }
+    }
+}
+
+/// Errors found while loading the rules
+#[derive(Debug)]
+pub enum RulesError {
+    /// Failed to read the definitions file
+    Io(io::Error),
+
+    /// Definitions file is not valid JSON of the expected shape
+    Parse(serde_json::Error),
+
+    /// Definitions file has invalid values
+    Invalid(Vec<DefinitionError>),
+
+    /// Error found in the definitions file at the given path
+    File(String, Box<RulesError>),
+
+    /// The settings do not fit together
+    Config(ConfigError),
+}
+
+impl fmt::Display for RulesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RulesError::Io(e) => write!(f, "Rules IO error: {}", e),
+            RulesError::Parse(e) => write!(f, "Invalid rules: {}", e),
+            RulesError::Invalid(errors) => {
+                write!(f, "Invalid rules:")?;
+                for error in errors {
+                    write!(f, "\n  {}", error)?;
+                }
+                Ok(())
+            }
+            RulesError::File(path, e) => write!(f, "{}: {}", path, e),
+            RulesError::Config(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for RulesError {}
+
+impl From<io::Error> for RulesError {
+    fn from(e: io::Error) -> RulesError {
+        RulesError::Io(e)
+    }
+}
+
+impl From<ConfigError> for RulesError {
+    fn from(e: ConfigError) -> RulesError {
+        RulesError::Config(e)
+    }
+}
+
+impl From<serde_json::Error> for RulesError {
+    fn from(e: serde_json::Error) -> RulesError {
+        RulesError::Parse(e)
+    }
+}
+
+/// Named difficulty levels, each adjusting the rules with a `Preset`
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+    Nightmare
+}
+
+/// Every difficulty, from easiest to hardest
+pub const DIFFICULTIES: [Difficulty; 4] = [
+    Difficulty::Easy,
+    Difficulty::Normal,
+    Difficulty::Hard,
+    Difficulty::Nightmare,
+];
+
+/// Settings changed by a difficulty
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Preset {
+    /// Number of slots in the dungeon row, including the deck
+    pub dungeon_size: usize,
+
+    /// Number of cards withheld at the beginning of the game
+    pub payments: u32,
+
+    /// Number of cards in the starting hand
+    pub hand_size: u8,
+
+    /// Weakest monster base strength that can appear in the dungeon
+    pub min_strength: u8,
+
+    /// Strongest monster base strength that can appear in the dungeon
+    pub max_strength: u8,
+
+    /// How cards lost to Noxious monsters are picked
+    pub noxious: NoxiousDiscard,
+}
+
+impl Difficulty {
+    /// Settings used by this difficulty. `Normal` is the standard game.
+    pub fn preset(self) -> Preset {
+        match self {
+            Difficulty::Easy => Preset {
+                dungeon_size: 10,
+                payments: 3,
+                hand_size: 6,
+                min_strength: 0,
+                max_strength: 3,
+                noxious: NoxiousDiscard::Chosen,
+            },
+            Difficulty::Normal => Preset {
+                dungeon_size: MONSTER_DECK_SIZE,
+                payments: PAYMENTS,
+                hand_size: HAND_SIZE,
+                min_strength: 0,
+                max_strength: u8::MAX,
+                noxious: NoxiousDiscard::Random,
+            },
+            Difficulty::Hard => Preset {
+                dungeon_size: 16,
+                payments: 6,
+                hand_size: HAND_SIZE,
+                min_strength: 0,
+                max_strength: u8::MAX,
+                noxious: NoxiousDiscard::Random,
+            },
+            Difficulty::Nightmare => Preset {
+                dungeon_size: 17,
+                payments: 7,
+                hand_size: 4,
+                min_strength: 1,
+                max_strength: u8::MAX,
+                noxious: NoxiousDiscard::Random,
+            },
+        }
+    }
+
+    /// Get the difficulty called `name`, ignoring case
+    pub fn by_name(name: &str) -> Option<Difficulty> {
+        DIFFICULTIES.iter().copied()
+            .find(|difficulty| difficulty.to_string().eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+/// Settings a game is created from
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rules {
+    /// Monsters the dungeon is drawn from
+    pub monsters: Vec<MonsterDef>,
+
+    /// Cards the action deck is built from
+    pub deck: Vec<CardCount>,
+
+    /// Number of slots in the dungeon row, including the deck in the first slot
+    pub dungeon_size: usize,
+
+    /// Number of cards withheld at the beginning of the game
+    pub payments: u32,
+
+    /// Number of cards in the starting hand
+    pub hand_size: u8,
+
+    /// How cards lost to Noxious monsters are picked
+    pub noxious: NoxiousDiscard,
+
+    /// Difficulty these rules were adjusted for
+    pub difficulty: Difficulty,
+
+    /// Plays that would waste the card are rejected instead of spending it
+    pub strict: bool,
+
+    /// Number of times the discard pile is shuffled back into the deck once it runs out
+    pub reshuffles: u32,
+}
+
+impl Rules {
+    /// Read the rules from `MONSTERS_FILE` and `DECK_FILE` in `asset_dir`, checking that every
+    /// image they refer to exists
+    pub fn load(asset_dir: impl AsRef<Path>) -> Result<Rules, RulesError> {
+        let asset_dir = asset_dir.as_ref();
+
+        let monsters = read_file(asset_dir, MONSTERS_FILE, parse_monsters)?;
+        let deck = read_file(asset_dir, DECK_FILE, parse_deck)?;
+
+        let rules = Rules::new(monsters, deck);
+        rules.validate()?;
+        Ok(rules)
+    }
+
+    /// Standard rules using the given monsters and deck recipe
+    fn new(monsters: Vec<MonsterDef>, deck: Vec<CardCount>) -> Rules {
+        Rules {
+            monsters,
+            deck,
+            dungeon_size: MONSTER_DECK_SIZE,
+            payments: PAYMENTS,
+            hand_size: HAND_SIZE,
+            noxious: NoxiousDiscard::Random,
+            difficulty: Difficulty::Normal,
+            strict: false,
+            reshuffles: 0,
+        }
+    }
+
+    /// Adjust these rules to `difficulty`, keeping only the monsters eligible for it
+    pub fn with_difficulty(mut self, difficulty: Difficulty) -> Result<Rules, RulesError> {
+        let preset = difficulty.preset();
+
+        self.monsters.retain(|def| {
+            def.strength >= preset.min_strength && def.strength <= preset.max_strength
+        });
+        self.dungeon_size = preset.dungeon_size;
+        self.payments = preset.payments;
+        self.hand_size = preset.hand_size;
+        self.noxious = preset.noxious;
+        self.difficulty = difficulty;
+
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Use a dungeon row of `size` slots, including the deck in the first slot
+    pub fn with_dungeon_size(mut self, size: usize) -> Result<Rules, RulesError> {
+        self.dungeon_size = size;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Withhold `payments` cards at the beginning of the game
+    pub fn with_payments(mut self, payments: u32) -> Result<Rules, RulesError> {
+        self.payments = payments;
+        self.validate()?;
+        Ok(self)
+    }
+
+    /// Check that the settings fit together
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let max = self.monsters.len() + 1;
+        if self.dungeon_size < MIN_DUNGEON_SIZE || self.dungeon_size > max {
+            return Err(ConfigError::DungeonSize {
+                size: self.dungeon_size,
+                min: MIN_DUNGEON_SIZE,
+                max
+            });
+        }
+
+        if self.hand_size == 0 {
+            return Err(ConfigError::EmptyHand);
+        }
+
+        // The payments are taken from the deck before the starting hand is dealt
+        let found: u32 = self.deck.iter().map(|card| card.count).sum();
+        let needed = self.payments + self.hand_size as u32;
+        if found < needed {
+            return Err(ConfigError::TooFewCards { found, needed });
+        }
+
+        Ok(())
+    }
+
+    /// Unshuffled action deck built from the deck recipe
+    pub fn deck_cards(&self) -> Vec<u8> {
+        let mut cards = Vec::new();
+        for card in &self.deck {
+            for _ in 0..card.count {
+                cards.push(card.value);
+            }
+        }
+
+        cards
+    }
+}
+
+impl Default for Rules {
+    /// Rules using the monster definitions and deck recipe shipped with the game
+    fn default() -> Rules {
+        let monsters = parse_monsters(DEFAULT_MONSTERS, None)
+            .expect("Built-in monster definitions are invalid");
+
+        Rules::new(monsters, default_deck())
+    }
+}
+
+/// Deck recipe shipped with the game
+fn default_deck() -> Vec<CardCount> {
+    parse_deck(DEFAULT_DECK, None).expect("Built-in deck recipe is invalid")
+}
+
+/// Read `file` from `asset_dir` with the given `parse` function, attaching the path to any error
+fn read_file<T>(asset_dir: &Path, file: &str,
+        parse: fn(&str, Option<&Path>) -> Result<T, RulesError>) -> Result<T, RulesError> {
+    let path = asset_dir.join(file);
+
+    fs::read_to_string(&path)
+        .map_err(RulesError::from)
+        .and_then(|source| parse(&source, Some(asset_dir)))
+        .map_err(|e| RulesError::File(path.display().to_string(), Box::new(e)))
+}
+
+/// Parse and validate the monster definitions in `source`. Images are only checked if an
+/// `asset_dir` is given.
+pub fn parse_monsters(source: &str, asset_dir: Option<&Path>) -> Result<Vec<MonsterDef>, RulesError> {
+    let raw: Vec<RawMonsterDef> = serde_json::from_str(source)?;
+    let lines = entry_lines(source);
+
+    let mut errors = Vec::new();
+    let mut monsters = Vec::new();
+    let mut seen = HashMap::new();
+
+    for (index, raw) in raw.into_iter().enumerate() {
+        let line = lines.get(index).copied().unwrap_or(1);
+        let mut error = |problem| errors.push(DefinitionError { line, problem });
+
+        if let Some(&first_line) = seen.get(&raw.name) {
+            error(DefinitionProblem::DuplicateName { name: raw.name.clone(), first_line });
+        } else {
+            seen.insert(raw.name.clone(), line);
+        }
+
+        if raw.strength > MAX_MONSTER_STRENGTH {
+            error(DefinitionProblem::InvalidStrength(raw.strength));
+        }
+
+        let ability = match raw.ability.as_deref() {
+            None => None,
+            Some("Noxious") => Some(Ability::Noxious),
+            Some("Rally") => Some(Ability::Rally),
+            Some("Reign") => Some(Ability::Reign),
+            Some(other) => {
+                error(DefinitionProblem::UnknownAbility(other.to_string()));
+                None
+            }
+        };
+
+        let mut to_slay = Vec::new();
+        for hit in &raw.to_slay {
+            match hit.as_str() {
+                "Melee" => to_slay.push(ToSlay::Melee),
+                "Range" => to_slay.push(ToSlay::Range),
+                "Move" => to_slay.push(ToSlay::Move),
+                other => error(DefinitionProblem::UnknownToSlay(other.to_string())),
+            }
+        }
+
+        if raw.to_slay.is_empty() {
+            error(DefinitionProblem::NoToSlay);
+        }
+
+        if let Some(asset_dir) = asset_dir {
+            if !asset_dir.join(&raw.image).is_file() {
+                error(DefinitionProblem::MissingImage(raw.image.clone()));
+            }
+        }
+
+        monsters.push(MonsterDef {
+            name: raw.name,
+            strength: raw.strength,
+            ability,
+            to_slay,
+            image: raw.image,
+        });
+    }
+
+    if !errors.is_empty() {
+        return Err(RulesError::Invalid(errors));
+    }
+
+    Ok(monsters)
+}
+
+/// Parse and validate the deck recipe in `source`. Images are only checked if an `asset_dir` is
+/// given.
+pub fn parse_deck(source: &str, asset_dir: Option<&Path>) -> Result<Vec<CardCount>, RulesError> {
+    let deck: Vec<CardCount> = serde_json::from_str(source)?;
+    let lines = entry_lines(source);
+
+    let mut errors = Vec::new();
+    let mut seen = HashMap::new();
+
+    for (index, card) in deck.iter().enumerate() {
+        let line = lines.get(index).copied().unwrap_or(1);
+        let mut error = |problem| errors.push(DefinitionError { line, problem });
+
+        if card.value > MAX_CARD_VALUE {
+            error(DefinitionProblem::InvalidCardValue(card.value));
+        }
+
+        if let Some(&first_line) = seen.get(&card.value) {
+            error(DefinitionProblem::DuplicateCardValue { value: card.value, first_line });
+        } else {
+            seen.insert(card.value, line);
+        }
+
+        if let (Some(asset_dir), Some(image)) = (asset_dir, &card.image) {
+            if !asset_dir.join(image).is_file() {
+                error(DefinitionProblem::MissingImage(image.clone()));
+            }
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(RulesError::Invalid(errors));
+    }
+
+    Ok(deck)
+}
+
+/// Line on which each element of the top level JSON array in `source` starts
+fn entry_lines(source: &str) -> Vec<usize> {
+    let mut lines = Vec::new();
+    let mut line = 1;
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+
+    for c in source.chars() {
+        if c == '\n' {
+            line += 1;
+        }
+
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match c {
+            '"' => in_string = true,
+            '{' | '[' => {
+                if depth == 1 {
+                    lines.push(line);
+                }
+                depth += 1;
+            }
+            '}' | ']' => depth -= 1,
+            _ => {}
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Problems of the definitions rejected by `result`, along with their lines
+    fn problems<T: fmt::Debug>(result: Result<T, RulesError>) -> Vec<(usize, DefinitionProblem)> {
+        match result {
+            Err(RulesError::Invalid(errors)) =>
+                errors.into_iter().map(|error| (error.line, error.problem)).collect(),
+            other => panic!("Expected invalid definitions, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn built_in_rules_are_valid() {
+        let rules = Rules::default();
+        assert!(rules.validate().is_ok());
+
+        for &difficulty in DIFFICULTIES.iter() {
+            let adjusted = rules.clone().with_difficulty(difficulty).unwrap();
+            assert_eq!(adjusted.difficulty, difficulty);
+        }
+    }
+
+    #[test]
+    fn monster_problems_are_reported_with_their_line() {
+        let source = r#"[
+    { "name": "Goblin", "strength": 1, "ability": null, "to_slay": ["Melee"], "image": "" },
+    { "name": "Titan", "strength": 254, "ability": "Rally", "to_slay": ["Melee"],
+      "image": "" },
+    { "name": "Goblin", "strength": 2, "ability": "Charm", "to_slay": [], "image": "" }
+]"#;
+
+        assert_eq!(problems(parse_monsters(source, None)), vec![
+            (3, DefinitionProblem::InvalidStrength(254)),
+            (5, DefinitionProblem::DuplicateName { name: "Goblin".to_string(), first_line: 2 }),
+            (5, DefinitionProblem::UnknownAbility("Charm".to_string())),
+            (5, DefinitionProblem::NoToSlay),
+        ]);
+    }
+
+    #[test]
+    fn strongest_monster_still_fits_with_rally() {
+        let source = r#"[
+    { "name": "Titan", "strength": 253, "ability": null, "to_slay": ["Move"], "image": "" }
+]"#;
+
+        let monsters = parse_monsters(source, None).unwrap();
+        assert_eq!(monsters[0].strength, MAX_MONSTER_STRENGTH);
+        assert!(MAX_MONSTER_STRENGTH.checked_add(2).is_some());
+    }
+
+    #[test]
+    fn card_problems_are_reported_with_their_line() {
+        let source = r#"[
+    { "value": 1, "count": 8 },
+    { "value": 7, "count": 2 },
+    { "value": 1, "count": 3 }
+]"#;
+
+        assert_eq!(problems(parse_deck(source, None)), vec![
+            (3, DefinitionProblem::InvalidCardValue(7)),
+            (4, DefinitionProblem::DuplicateCardValue { value: 1, first_line: 2 }),
+        ]);
+    }
+
+    #[test]
+    fn invalid_json_is_a_parse_error() {
+        assert!(matches!(parse_deck("[{ \"value\": 1 }]", None), Err(RulesError::Parse(_))));
+        assert!(matches!(parse_deck("[{ \"value\": 1, \"count\": 1, \"colour\": 2 }]", None),
+                         Err(RulesError::Parse(_))));
+    }
+
+    #[test]
+    fn settings_that_do_not_fit_are_rejected() {
+        let rules = Rules::default();
+        let max = rules.monsters.len() + 1;
+
+        assert!(rules.clone().with_dungeon_size(MIN_DUNGEON_SIZE).is_ok());
+        assert!(rules.clone().with_dungeon_size(max).is_ok());
+        assert!(matches!(rules.clone().with_dungeon_size(1),
+                         Err(RulesError::Config(ConfigError::DungeonSize { size: 1, .. }))));
+        assert!(matches!(rules.clone().with_dungeon_size(max + 1),
+                         Err(RulesError::Config(ConfigError::DungeonSize { .. }))));
+
+        let empty_hand = Rules { hand_size: 0, ..rules.clone() };
+        assert_eq!(empty_hand.validate(), Err(ConfigError::EmptyHand));
+
+        let found = rules.deck.iter().map(|card| card.count).sum::<u32>();
+        let too_few = Rules { payments: found, ..rules };
+        assert_eq!(too_few.validate(),
+                   Err(ConfigError::TooFewCards { found, needed: found + too_few.hand_size as u32 }));
+    }
+}
@@ -0,0 +1,147 @@
+//! Save file holding an in-progress game so it can be resumed on the next launch
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::GameState;
+use crate::replay::Replay;
+
+/// Current version of the save file format
+pub const SAVE_VERSION: u32 = 1;
+
+/// Errors found while reading or writing a save file
+#[derive(Debug)]
+pub enum SaveError {
+    /// Failed to read or write the save file
+    Io(io::Error),
+
+    /// Save file is not valid
+    Parse(serde_json::Error),
+
+    /// Save file was written by an unknown version of the format
+    UnsupportedVersion(u32),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(e) => write!(f, "Save IO error: {}", e),
+            SaveError::Parse(e) => write!(f, "Invalid save: {}", e),
+            SaveError::UnsupportedVersion(version) =>
+                write!(f, "Unsupported save version {} (expected {})", version, SAVE_VERSION),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(e: io::Error) -> SaveError {
+        SaveError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(e: serde_json::Error) -> SaveError {
+        SaveError::Parse(e)
+    }
+}
+
+/// Snapshot of an in-progress game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    /// Version of the save format
+    pub version: u32,
+
+    /// Full rules state of the game
+    pub game_state: GameState,
+
+    /// Commands applied so far, so the replay of a resumed game is complete
+    pub replay: Replay,
+}
+
+impl SaveGame {
+    pub fn new(game_state: GameState, replay: Replay) -> SaveGame {
+        SaveGame {
+            version: SAVE_VERSION,
+            game_state,
+            replay,
+        }
+    }
+
+    /// Write this save to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SaveError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    /// Read a save from `path`. Returns `Ok(None)` if there is no save file.
+    pub fn load(path: impl AsRef<Path>) -> Result<Option<SaveGame>, SaveError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+
+        let save: SaveGame = serde_json::from_str(&data)?;
+
+        if save.version != SAVE_VERSION {
+            return Err(SaveError::UnsupportedVersion(save.version));
+        }
+
+        Ok(Some(save))
+    }
+
+    /// Remove the save file at `path`, if there is one
+    pub fn delete(path: impl AsRef<Path>) -> Result<(), SaveError> {
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{play_out, Random, Strategy};
+
+    #[test]
+    fn restored_game_continues_like_the_original() {
+        let path = std::env::temp_dir().join(format!("maverick-save-{}.json", std::process::id()));
+
+        for seed in 0..20 {
+            let mut game = GameState::new(seed);
+            let mut replay = Replay::new(seed);
+            let mut random = Random::new(seed);
+
+            // Play the first few turns before saving
+            while game.turn < 4 && !game.is_over() {
+                let turn = game.turn;
+                let command = random.choose(&game).unwrap();
+                let outcome = game.apply(command).unwrap_or_else(|e| panic!("{}\n{}", e, game));
+                replay.record(turn, command, &outcome);
+            }
+
+            SaveGame::new(game.clone(), replay.clone()).save(&path).unwrap();
+            let restored = SaveGame::load(&path).unwrap().expect("Save file is missing");
+            let (mut resumed, mut resumed_replay) = (restored.game_state, restored.replay);
+
+            // The same choices from both must reach the same end, Noxious discards included
+            play_out(&mut game, &mut Random::new(seed), &mut replay).unwrap();
+            play_out(&mut resumed, &mut Random::new(seed), &mut resumed_replay).unwrap();
+
+            assert_eq!(resumed_replay, replay, "Seed {} diverged\n{}", seed, resumed);
+            let json = |game: &GameState| serde_json::to_string(game).unwrap();
+            assert_eq!(json(&resumed), json(&game));
+            assert!(resumed_replay.verify().is_ok());
+        }
+
+        SaveGame::delete(&path).unwrap();
+        assert!(SaveGame::load(&path).unwrap().is_none());
+    }
+}
@@ -0,0 +1,304 @@
+//! Local high-score table of every finished game, kept as one JSON line per run so a new score is
+//! appended without rewriting the file
+
+use std::fmt;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::GameState;
+use crate::rules::Difficulty;
+
+/// Errors found while reading or writing the score file
+#[derive(Debug)]
+pub enum ScoreError {
+    /// Failed to read or write the score file
+    Io(io::Error),
+
+    /// Line of the score file is not a valid entry
+    Parse {
+        line: usize,
+        error: serde_json::Error,
+    },
+}
+
+impl fmt::Display for ScoreError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScoreError::Io(e) => write!(f, "Score IO error: {}", e),
+            ScoreError::Parse { line, error } =>
+                write!(f, "Invalid score on line {}: {}", line, error),
+        }
+    }
+}
+
+impl std::error::Error for ScoreError {}
+
+impl From<io::Error> for ScoreError {
+    fn from(e: io::Error) -> ScoreError {
+        ScoreError::Io(e)
+    }
+}
+
+/// Final score of a single finished game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScoreEntry {
+    /// Total score of the game
+    pub score: u32,
+
+    /// Cards removed from the deck at the start of the game, worth 3 points each
+    pub payments: u32,
+
+    /// Trophies gathered during the game, worth 2 points each
+    pub trophies: u32,
+
+    /// Cards left in the hand and deck at the end of the game, worth 1 point each
+    pub cards_left: u32,
+
+    /// Every monster was slain
+    pub won: bool,
+
+    /// Seed the game was created from
+    pub seed: u64,
+
+    /// Seconds since the Unix epoch when the game finished
+    pub timestamp: u64,
+
+    /// Difficulty of the rules the game was played with
+    pub difficulty: Difficulty,
+
+    /// Number of slots in the dungeon row, including the deck
+    pub dungeon_size: usize,
+
+    /// Wasted card plays were rejected
+    pub strict: bool,
+
+    /// Undo was disabled
+    pub ironman: bool,
+}
+
+impl ScoreEntry {
+    /// Score of the finished `game`, recorded at `timestamp` seconds since the Unix epoch
+    pub fn new(game: &GameState, ironman: bool, timestamp: u64) -> ScoreEntry {
+        ScoreEntry {
+            score: game.score(),
+            payments: game.payments,
+            trophies: game.trophies,
+            cards_left: (game.hand.len() + game.deck.len()) as u32,
+            won: game.is_won(),
+            seed: game.seed,
+            timestamp,
+            difficulty: game.difficulty,
+            dungeon_size: game.dungeon_size(),
+            strict: game.strict,
+            ironman,
+        }
+    }
+
+    /// Day the game finished as `YYYY-MM-DD` in UTC
+    pub fn date(&self) -> String {
+        let (year, month, day) = civil_from_days((self.timestamp / 86_400) as i64);
+        format!("{:04}-{:02}-{:02}", year, month, day)
+    }
+}
+
+/// Which entries of the table to list
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct ScoreFilter {
+    /// Only won games if `Some(true)`, only lost games if `Some(false)`
+    pub won: Option<bool>,
+
+    /// Only games played at this difficulty
+    pub difficulty: Option<Difficulty>,
+
+    /// Only games played in a dungeon row of this many slots
+    pub dungeon_size: Option<usize>,
+
+    /// Only games played with strict rules if `Some(true)`, only without if `Some(false)`
+    pub strict: Option<bool>,
+}
+
+impl ScoreFilter {
+    /// Only the games played with the same rules as `game`
+    pub fn same_rules(game: &GameState) -> ScoreFilter {
+        ScoreFilter {
+            won: None,
+            difficulty: Some(game.difficulty),
+            dungeon_size: Some(game.dungeon_size()),
+            strict: Some(game.strict),
+        }
+    }
+
+    /// The `entry` is listed by this filter
+    pub fn matches(&self, entry: &ScoreEntry) -> bool {
+        self.won.iter().all(|&won| entry.won == won)
+            && self.difficulty.iter().all(|&difficulty| entry.difficulty == difficulty)
+            && self.dungeon_size.iter().all(|&size| entry.dungeon_size == size)
+            && self.strict.iter().all(|&strict| entry.strict == strict)
+    }
+}
+
+/// Every score recorded in the score file, in the order the games finished
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ScoreTable {
+    pub entries: Vec<ScoreEntry>,
+}
+
+impl ScoreTable {
+    /// Read the table from `path`. Returns an empty table if there is no score file.
+    pub fn load(path: impl AsRef<Path>) -> Result<ScoreTable, ScoreError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(ScoreTable::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut entries = Vec::new();
+        for (number, line) in data.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let entry = serde_json::from_str(line)
+                .map_err(|error| ScoreError::Parse { line: number + 1, error })?;
+            entries.push(entry);
+        }
+
+        Ok(ScoreTable { entries })
+    }
+
+    /// Append `entry` to the score file at `path`, creating the file if needed
+    pub fn append(path: impl AsRef<Path>, entry: &ScoreEntry) -> Result<(), ScoreError> {
+        // Serializing plain numbers and strings cannot fail
+        let line = serde_json::to_string(entry).expect("Failed to serialize score");
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)?;
+        Ok(())
+    }
+
+    /// Append the score of the finished `game` to the score file at `path`, timestamped now
+    pub fn record(path: impl AsRef<Path>, game: &GameState, ironman: bool)
+            -> Result<(), ScoreError> {
+        ScoreTable::append(path, &ScoreEntry::new(game, ironman, timestamp()))
+    }
+
+    /// Best `count` entries matching `filter`, highest score first. Ties are listed in the order
+    /// the games finished.
+    pub fn top(&self, filter: &ScoreFilter, count: usize) -> Vec<&ScoreEntry> {
+        let mut entries: Vec<&ScoreEntry> = self.entries.iter()
+            .filter(|entry| filter.matches(entry))
+            .collect();
+
+        // The sort is stable, so earlier games stay ahead of later ones with the same score
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        entries.truncate(count);
+        entries
+    }
+}
+
+/// Seconds since the Unix epoch, or 0 if the clock is set before it
+pub fn timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+/// Year, month and day of the `days` since 1970-01-01 in the proleptic Gregorian calendar
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Shift the epoch to 0000-03-01 so leap days fall at the end of each 400 year era
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Entry of a game of `score` points finished at `timestamp`
+    fn entry(score: u32, won: bool, difficulty: Difficulty, timestamp: u64) -> ScoreEntry {
+        ScoreEntry {
+            score,
+            payments: 0,
+            trophies: 0,
+            cards_left: 0,
+            won,
+            seed: 0,
+            timestamp,
+            difficulty,
+            dungeon_size: difficulty.preset().dungeon_size,
+            strict: false,
+            ironman: false,
+        }
+    }
+
+    #[test]
+    fn days_are_converted_to_dates() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(59), (1970, 3, 1));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19_723), (2024, 1, 1));
+        assert_eq!(entry(0, false, Difficulty::Normal, 1_709_251_199).date(), "2024-02-29");
+    }
+
+    #[test]
+    fn filter_matches_every_set_field() {
+        let game = entry(10, true, Difficulty::Hard, 0);
+        assert!(ScoreFilter::default().matches(&game));
+        assert!(ScoreFilter { won: Some(true), ..ScoreFilter::default() }.matches(&game));
+        assert!(!ScoreFilter { won: Some(false), ..ScoreFilter::default() }.matches(&game));
+
+        let same = ScoreFilter {
+            difficulty: Some(Difficulty::Hard),
+            dungeon_size: Some(game.dungeon_size),
+            strict: Some(false),
+            ..ScoreFilter::default()
+        };
+        assert!(same.matches(&game));
+        assert!(!ScoreFilter { difficulty: Some(Difficulty::Easy), ..same }.matches(&game));
+        assert!(!ScoreFilter { dungeon_size: Some(game.dungeon_size + 1), ..same }.matches(&game));
+        assert!(!ScoreFilter { strict: Some(true), ..same }.matches(&game));
+    }
+
+    #[test]
+    fn top_lists_the_best_matching_scores_in_order() {
+        let table = ScoreTable {
+            entries: vec![
+                entry(5, false, Difficulty::Normal, 1),
+                entry(9, true, Difficulty::Normal, 2),
+                entry(7, true, Difficulty::Hard, 3),
+                entry(9, true, Difficulty::Normal, 4),
+                entry(3, true, Difficulty::Normal, 5),
+            ],
+        };
+
+        let timestamps = |entries: Vec<&ScoreEntry>| -> Vec<u64> {
+            entries.iter().map(|entry| entry.timestamp).collect()
+        };
+
+        // Equal scores stay in the order the games finished
+        assert_eq!(timestamps(table.top(&ScoreFilter::default(), 3)), vec![2, 4, 3]);
+
+        let normal_wins = ScoreFilter {
+            won: Some(true),
+            difficulty: Some(Difficulty::Normal),
+            ..ScoreFilter::default()
+        };
+        assert_eq!(timestamps(table.top(&normal_wins, 10)), vec![2, 4, 5]);
+        assert!(table.top(&normal_wins, 0).is_empty());
+    }
+}
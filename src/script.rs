@@ -0,0 +1,53 @@
+//! Text commands shared by the terminal frontend and scripted games.
+//!
+//! A command is one line of words: `N move left|right`, `N range left|right`, `N melee`, each
+//! optionally with `c` after the card for the companion, `N swap`, `discard N` and `end`. Cards
+//! are numbered from 1 in hand order and directions can be shortened to `l` and `r`.
+
+use crate::engine::*;
+
+/// Parse a single command line. Returns `None` if the line is not a command.
+pub fn parse_command(line: &str) -> Option<Command> {
+    let line = line.to_lowercase();
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    match words.as_slice() {
+        ["e"] | ["end"] => Some(Command::EndTurn),
+        ["discard", card] => Some(Command::Play(Action::Discard, parse_card(card)?)),
+        _ => parse_play(&words),
+    }
+}
+
+/// Hand index of the card numbered from 1
+fn parse_card(word: &str) -> Option<usize> {
+    match word.parse::<usize>() {
+        Ok(card) if card > 0 => Some(card - 1),
+        _ => None
+    }
+}
+
+/// Parse a card play such as `2 c range left`
+fn parse_play(words: &[&str]) -> Option<Command> {
+    let hand_index = parse_card(words.first()?)?;
+
+    let (entity, words) = match words.get(1) {
+        Some(&"c") | Some(&"companion") => (Entity::Companion, &words[2..]),
+        _ => (Entity::Character, &words[1..]),
+    };
+
+    let direction = match words.get(1) {
+        Some(&"l") | Some(&"left") => Some(Direction::Left),
+        Some(&"r") | Some(&"right") => Some(Direction::Right),
+        _ => None
+    };
+
+    let action = match (words.first(), direction, words.len()) {
+        (Some(&"move"), Some(direction), 2) => Action::Move(entity, direction),
+        (Some(&"range"), Some(direction), 2) => Action::Range(entity, direction),
+        (Some(&"melee"), None, 1) => Action::Melee(entity),
+        (Some(&"swap"), None, 1) if entity == Entity::Character => Action::Swap,
+        _ => return None
+    };
+
+    Some(Command::Play(action, hand_index))
+}
@@ -0,0 +1,421 @@
+//! Batch simulation of seeded games played by a strategy, collecting statistics for tuning the
+//! rules
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+
+use serde::Serialize;
+
+use crate::engine::*;
+use crate::replay::Replay;
+use crate::rules::{Difficulty, Rules};
+use crate::solver::solve_with_limit;
+use crate::strategy::{self, play_out};
+use crate::util::ratio;
+
+/// Result of a single simulated game
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GameResult {
+    /// Seed the game was created from
+    pub seed: u64,
+
+    /// Every monster was slain
+    pub won: bool,
+
+    /// Final score of the game
+    pub score: u32,
+
+    /// Trophies gathered during the game
+    pub trophies: u32,
+
+    /// Number of turns the game lasted
+    pub turns: u32,
+
+    /// Monsters slain during the game
+    pub killed: u32,
+
+    /// Best result the solver found for the seed, if the games were solved
+    pub solved: Option<SolvedResult>,
+}
+
+/// Best result the solver found for the seed of a game
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+pub struct SolvedResult {
+    /// Some line of play slays every monster
+    pub winnable: bool,
+
+    /// Highest final score found
+    pub best_score: u32,
+
+    /// Every position was searched, so `winnable` and `best_score` are exact rather than the
+    /// best found within the position limit
+    pub complete: bool,
+}
+
+/// How often a single monster was slain over every game it appeared in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MonsterRecord {
+    /// Name of the monster
+    pub name: String,
+
+    /// Number of games the monster was in the dungeon
+    pub games: u32,
+
+    /// Number of games the monster was slain
+    pub killed: u32,
+}
+
+impl MonsterRecord {
+    /// Number of games the monster survived until the end
+    pub fn survived(&self) -> u32 {
+        self.games - self.killed
+    }
+
+    /// Fraction of the games the monster was in that it was slain
+    pub fn kill_rate(&self) -> f64 {
+        ratio(self.killed, self.games)
+    }
+}
+
+/// Statistics over a batch of simulated games
+#[derive(Debug, Clone)]
+pub struct Report {
+    /// Name of the strategy playing the games
+    pub strategy: String,
+
+    /// Difficulty of the rules the games were played with
+    pub difficulty: Difficulty,
+
+    /// Result of every game, in the order they were played
+    pub games: Vec<GameResult>,
+
+    /// Kill record of every monster that appeared in at least one game, sorted by name
+    pub monsters: Vec<MonsterRecord>,
+}
+
+/// Summary numbers of a `Report`, as written to JSON
+#[derive(Serialize)]
+struct Summary<'a> {
+    strategy: &'a str,
+    difficulty: Difficulty,
+    games: usize,
+    win_rate: f64,
+    average_score: f64,
+    average_trophies: f64,
+    winnable_rate: Option<f64>,
+    average_best_score: Option<f64>,
+    score_distribution: BTreeMap<u32, usize>,
+    monsters: Vec<MonsterSummary<'a>>,
+    results: &'a [GameResult],
+}
+
+/// Kill record of a monster along with its rates, as written to JSON
+#[derive(Serialize)]
+struct MonsterSummary<'a> {
+    name: &'a str,
+    games: u32,
+    killed: u32,
+    survived: u32,
+    kill_rate: f64,
+}
+
+/// Errors preventing a batch of games from being simulated
+#[derive(Debug)]
+pub enum SimulateError {
+    /// There is no strategy of the given name
+    UnknownStrategy(String),
+
+    /// The rules cannot create a game
+    Rule(RuleError),
+}
+
+impl fmt::Display for SimulateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SimulateError::UnknownStrategy(name) =>
+                write!(f, "Unknown strategy {}, expected one of {:?}", name, strategy::STRATEGIES),
+            SimulateError::Rule(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SimulateError {}
+
+impl From<RuleError> for SimulateError {
+    fn from(e: RuleError) -> SimulateError {
+        SimulateError::Rule(e)
+    }
+}
+
+/// Play `games` games of the given `rules` with the strategy called `strategy_name`, using the
+/// seeds `first_seed` onwards. If a `solve_limit` is given, every seed is also searched by the
+/// solver for up to that many positions to find the best result possible.
+pub fn simulate(strategy_name: &str, rules: &Rules, first_seed: u64, games: usize,
+        solve_limit: Option<usize>) -> Result<Report, SimulateError> {
+    let mut results = Vec::with_capacity(games);
+    let mut monsters: BTreeMap<String, MonsterRecord> = BTreeMap::new();
+
+    for seed in (first_seed..).take(games) {
+        // Seed the strategy from the game too, so every result can be reproduced on its own
+        let mut strategy = strategy::by_name(strategy_name, seed)
+            .ok_or_else(|| SimulateError::UnknownStrategy(strategy_name.to_string()))?;
+        let mut game = GameState::with_rules(seed, rules)?;
+        let solved = solve_limit.map(|limit| {
+            let solution = solve_with_limit(&game, limit);
+            SolvedResult {
+                winnable: solution.winnable,
+                best_score: solution.best_score,
+                complete: solution.complete,
+            }
+        });
+
+        let mut replay = Replay::new(seed);
+        replay.rules = rules.clone();
+
+        if let Err(e) = play_out(&mut game, strategy.as_mut(), &mut replay) {
+            error!("Seed {}: {} played an invalid command: {}\n{}", seed, strategy_name, e, game);
+        }
+
+        let mut killed = 0;
+        for index in game.monsters.monster_slots() {
+            let name = &game.monsters.names[index];
+            let record = monsters.entry(name.clone()).or_insert_with(|| MonsterRecord {
+                name: name.clone(),
+                games: 0,
+                killed: 0,
+            });
+
+            record.games += 1;
+            if !game.monsters.alive[index] {
+                record.killed += 1;
+                killed += 1;
+            }
+        }
+
+        results.push(GameResult {
+            seed,
+            won: game.is_won(),
+            score: game.score(),
+            trophies: game.trophies,
+            turns: game.turn,
+            killed,
+            solved,
+        });
+    }
+
+    Ok(Report {
+        strategy: strategy_name.to_string(),
+        difficulty: rules.difficulty,
+        games: results,
+        monsters: monsters.into_values().collect(),
+    })
+}
+
+impl Report {
+    /// Fraction of the games that were won
+    pub fn win_rate(&self) -> f64 {
+        let wins = self.games.iter().filter(|result| result.won).count();
+        ratio(wins, self.games.len())
+    }
+
+    /// Mean final score
+    pub fn average_score(&self) -> f64 {
+        let total: u32 = self.games.iter().map(|result| result.score).sum();
+        ratio(total, self.games.len())
+    }
+
+    /// Mean number of trophies gathered per game
+    pub fn average_trophies(&self) -> f64 {
+        let total: u32 = self.games.iter().map(|result| result.trophies).sum();
+        ratio(total, self.games.len())
+    }
+
+    /// Solver results of the games that were solved
+    fn solved(&self) -> impl Iterator<Item = &SolvedResult> {
+        self.games.iter().filter_map(|result| result.solved.as_ref())
+    }
+
+    /// Fraction of the solved seeds where the solver found a win, or `None` if no game was
+    /// solved
+    pub fn winnable_rate(&self) -> Option<f64> {
+        let solved = self.solved().count() as u32;
+        let winnable = self.solved().filter(|solved| solved.winnable).count() as u32;
+        if solved == 0 { None } else { Some(ratio(winnable, solved)) }
+    }
+
+    /// Mean best score found by the solver, or `None` if no game was solved
+    pub fn average_best_score(&self) -> Option<f64> {
+        let solved = self.solved().count() as u32;
+        let total: u32 = self.solved().map(|solved| solved.best_score).sum();
+        if solved == 0 { None } else { Some(ratio(total, solved)) }
+    }
+
+    /// Number of games ending with each final score
+    pub fn score_distribution(&self) -> BTreeMap<u32, usize> {
+        let mut distribution = BTreeMap::new();
+        for result in &self.games {
+            *distribution.entry(result.score).or_insert(0) += 1;
+        }
+
+        distribution
+    }
+
+    /// Human readable summary of the report
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+
+        // Writing to a `String` cannot fail
+        let _ = writeln!(out, "Strategy:         {}", self.strategy);
+        let _ = writeln!(out, "Difficulty:       {}", self.difficulty);
+        let _ = writeln!(out, "Games:            {}", self.games.len());
+        let _ = writeln!(out, "Win rate:         {:.1}%", self.win_rate() * 100.0);
+        let _ = writeln!(out, "Average score:    {:.2}", self.average_score());
+        let _ = writeln!(out, "Average trophies: {:.2}", self.average_trophies());
+
+        if let (Some(winnable), Some(best)) = (self.winnable_rate(), self.average_best_score()) {
+            let complete = self.solved().filter(|solved| solved.complete).count();
+            let _ = writeln!(out, "\nSolved seeds:     {} ({} searched completely)",
+                             self.solved().count(), complete);
+            let _ = writeln!(out, "Winnable seeds:   {:.1}%", winnable * 100.0);
+            let _ = writeln!(out, "Average best:     {:.2}", best);
+        }
+
+        let _ = writeln!(out, "\nScore distribution:");
+        for (score, count) in self.score_distribution() {
+            let _ = writeln!(out, "{:>4} {:>6} {}", score, count,
+                             "#".repeat(count * 50 / self.games.len()));
+        }
+
+        let _ = writeln!(out, "\n{:<12} {:>6} {:>6} {:>8} {:>9}",
+                         "Monster", "Games", "Killed", "Survived", "Kill rate");
+        for monster in &self.monsters {
+            let _ = writeln!(out, "{:<12} {:>6} {:>6} {:>8} {:>8.1}%", monster.name, monster.games,
+                             monster.killed, monster.survived(), monster.kill_rate() * 100.0);
+        }
+
+        out
+    }
+
+    /// Comma separated tables of every game result followed by every monster's kill record,
+    /// separated by an empty line
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+
+        let _ = writeln!(out, "seed,won,score,trophies,turns,killed,winnable,best_score,solve_complete");
+        for result in &self.games {
+            // Games that were not solved leave the solver columns empty
+            let solved = match result.solved {
+                Some(solved) => format!("{},{},{}", solved.winnable, solved.best_score,
+                                        solved.complete),
+                None => ",,".to_string(),
+            };
+
+            let _ = writeln!(out, "{},{},{},{},{},{},{}", result.seed, result.won, result.score,
+                             result.trophies, result.turns, result.killed, solved);
+        }
+
+        let _ = writeln!(out);
+        let _ = writeln!(out, "monster,games,killed,survived,kill_rate");
+        for monster in &self.monsters {
+            let _ = writeln!(out, "{},{},{},{},{:.4}", monster.name, monster.games, monster.killed,
+                             monster.survived(), monster.kill_rate());
+        }
+
+        out
+    }
+
+    /// Summary statistics along with every game result as JSON
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        let summary = Summary {
+            strategy: &self.strategy,
+            difficulty: self.difficulty,
+            games: self.games.len(),
+            win_rate: self.win_rate(),
+            average_score: self.average_score(),
+            average_trophies: self.average_trophies(),
+            winnable_rate: self.winnable_rate(),
+            average_best_score: self.average_best_score(),
+            score_distribution: self.score_distribution(),
+            monsters: self.monsters.iter().map(|monster| MonsterSummary {
+                name: &monster.name,
+                games: monster.games,
+                killed: monster.killed,
+                survived: monster.survived(),
+                kill_rate: monster.kill_rate(),
+            }).collect(),
+            results: &self.games,
+        };
+
+        serde_json::to_string_pretty(&summary)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(seed: u64, won: bool, score: u32, solved: Option<SolvedResult>) -> GameResult {
+        GameResult { seed, won, score, trophies: 2, turns: 5, killed: 3, solved }
+    }
+
+    /// Report of three games, the first of which was solved
+    fn report() -> Report {
+        let solved = SolvedResult { winnable: true, best_score: 30, complete: false };
+        Report {
+            strategy: "greedy".to_string(),
+            difficulty: Difficulty::Hard,
+            games: vec![
+                result(1, true, 20, Some(solved)),
+                result(2, false, 8, None),
+                result(3, false, 8, None),
+            ],
+            monsters: vec![MonsterRecord { name: "Goblin".to_string(), games: 3, killed: 2 }],
+        }
+    }
+
+    #[test]
+    fn rates_and_distribution() {
+        let report = report();
+        assert_eq!(report.win_rate(), 1.0 / 3.0);
+        assert_eq!(report.average_score(), 12.0);
+        assert_eq!(report.average_trophies(), 2.0);
+        assert_eq!(report.winnable_rate(), Some(1.0));
+        assert_eq!(report.average_best_score(), Some(30.0));
+        assert_eq!(report.score_distribution().into_iter().collect::<Vec<_>>(),
+                   vec![(8, 2), (20, 1)]);
+        assert_eq!(report.monsters[0].survived(), 1);
+
+        let empty = Report { games: Vec::new(), monsters: Vec::new(), ..report };
+        assert_eq!(empty.win_rate(), 0.0);
+        assert_eq!(empty.winnable_rate(), None);
+    }
+
+    #[test]
+    fn csv_has_a_row_per_game_and_monster() {
+        let csv = report().to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines, vec![
+            "seed,won,score,trophies,turns,killed,winnable,best_score,solve_complete",
+            "1,true,20,2,5,3,true,30,false",
+            "2,false,8,2,5,3,,,",
+            "3,false,8,2,5,3,,,",
+            "",
+            "monster,games,killed,survived,kill_rate",
+            "Goblin,3,2,1,0.6667",
+        ]);
+    }
+
+    #[test]
+    fn json_holds_the_summary_and_every_result() {
+        let json: serde_json::Value = serde_json::from_str(&report().to_json().unwrap()).unwrap();
+        assert_eq!(json["strategy"], "greedy");
+        assert_eq!(json["difficulty"], "Hard");
+        assert_eq!(json["games"], 3);
+        assert_eq!(json["average_score"], 12.0);
+        assert_eq!(json["winnable_rate"], 1.0);
+        assert_eq!(json["score_distribution"]["8"], 2);
+        assert_eq!(json["monsters"][0]["survived"], 1);
+        assert_eq!(json["results"].as_array().unwrap().len(), 3);
+        assert_eq!(json["results"][1]["solved"], serde_json::Value::Null);
+    }
+}
@@ -0,0 +1,396 @@
+//! Exhaustive solver finding the best possible result of a game.
+//!
+//! Every card play removes a card from the game until the discard pile is reshuffled, which only
+//! happens a limited number of times, and ending the turn only moves cards to the hand. Once
+//! turns that change nothing are skipped the positions form a tree that can be searched to the
+//! end. Identical positions reached by different orders of play are only searched once.
+//!
+//! Small rule sets can be searched completely, but a full-size game has far too many positions.
+//! Use `solve_with_limit` for those, which gives the best result found within the limit.
+
+use std::collections::HashMap;
+
+use crate::engine::*;
+use crate::moves::legal_moves;
+
+/// Best result found for a game
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    /// Some line of play slays every monster, given by `winning_moves`
+    pub winnable: bool,
+
+    /// Maximum final score: 3 per payment, 2 per trophy and 1 per card left
+    pub best_score: u32,
+
+    /// Commands reaching `best_score`, in order. The best score does not always come from a
+    /// win, so these may leave monsters alive even if the game is `winnable`.
+    pub moves: Vec<Command>,
+
+    /// Commands slaying every monster, in order, or empty if no win was found. This is the
+    /// highest scoring win found, which can score less than `moves`.
+    pub winning_moves: Vec<Command>,
+
+    /// Number of distinct positions searched
+    pub positions: usize,
+
+    /// Every position was searched. If the search hit its position limit, `best_score` is only
+    /// the best score found and `winnable` is `false` if no win was found.
+    pub complete: bool,
+}
+
+/// Everything about a position that can affect the rest of the game. Trophies already gathered
+/// cannot, so positions differing only in trophies share a single search.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct Position {
+    /// Monsters alive
+    alive: Vec<bool>,
+
+    /// Number of each kind of hit on each monster that still counts towards slaying it
+    hits: Vec<[u8; 3]>,
+
+    player_index: usize,
+    player_kind: PlayerKind,
+    companion_index: usize,
+    companion_kind: CompanionKind,
+    hand: Vec<u8>,
+
+    /// Cards left to draw. Until the deck is reshuffled its length alone would do, but a
+    /// reshuffled deck depends on the order of the discard pile.
+    deck: Vec<u8>,
+
+    hand_limit: u8,
+    discarded: bool,
+    discard_pending: bool,
+    rng_position: u128,
+
+    /// Values of the discard pile in order, which only matter while it can still be reshuffled
+    /// into the deck
+    discards: Vec<u8>,
+    reshuffles: u32,
+}
+
+impl Position {
+    fn of(game: &GameState) -> Position {
+        let monsters = &game.monsters;
+
+        let mut hits = Vec::with_capacity(monsters.len());
+        for index in 0..monsters.len() {
+            // Hits beyond what the monster requires never matter, so only count the hits that
+            // do to let identical positions match
+            let mut counts = [0; 3];
+            for (i, &to_slay) in [ToSlay::Melee, ToSlay::Range, ToSlay::Move].iter().enumerate() {
+                let needed = monsters.to_slays[index].iter().filter(|&&x| x == to_slay).count();
+                let have = monsters.current_hits[index].iter().filter(|&&x| x == to_slay).count();
+                counts[i] = std::cmp::min(needed, have) as u8;
+            }
+            hits.push(counts);
+        }
+
+        // Leaving out the discard pile when it is never reshuffled lets different orders of play
+        // reaching the same position share a search
+        let discards = match game.reshuffles {
+            0 => Vec::new(),
+            _ => game.discard_pile.iter().map(|discarded| discarded.card).collect(),
+        };
+
+        Position {
+            alive: monsters.alive.clone(),
+            hits,
+            player_index: game.player_index,
+            player_kind: game.player_kind,
+            companion_index: game.companion_index,
+            companion_kind: game.companion_kind,
+            hand: game.hand.clone(),
+            deck: game.deck.clone(),
+            hand_limit: game.hand_limit,
+            discarded: game.discarded,
+            discard_pending: game.discard_pending,
+            rng_position: game.rng_position(),
+            discards,
+            reshuffles: game.reshuffles,
+        }
+    }
+}
+
+/// Best continuation from a position
+#[derive(Debug, Copy, Clone)]
+struct Entry {
+    /// Points still to be gained: 2 per trophy plus the cards left at the end
+    score: u32,
+
+    /// Command reaching `score`, or `None` if the game is over
+    best: Option<Command>,
+
+    /// Points still to be gained by the best continuation slaying every monster, or `None` if
+    /// there is none
+    win_score: Option<u32>,
+
+    /// Command starting the continuation reaching `win_score`, or `None` if the game is over
+    win: Option<Command>,
+}
+
+/// Depth first search over every continuation of a game
+struct Solver {
+    /// Best continuation of every position searched
+    memo: HashMap<Position, Entry>,
+
+    /// Maximum number of positions to search
+    limit: Option<usize>,
+
+    /// The search stopped early because of `limit`
+    incomplete: bool,
+}
+
+/// Search every continuation of `game` for the maximum score and whether the game can be won.
+/// Only finishes in reasonable time for small rule sets.
+pub fn solve(game: &GameState) -> Solution {
+    Solver::new(None).solve(game)
+}
+
+/// Same as `solve`, but stop after `limit` positions and return the best result found
+pub fn solve_with_limit(game: &GameState, limit: usize) -> Solution {
+    Solver::new(Some(limit)).solve(game)
+}
+
+impl Solver {
+    fn new(limit: Option<usize>) -> Solver {
+        Solver {
+            memo: HashMap::new(),
+            limit,
+            incomplete: false,
+        }
+    }
+
+    fn solve(mut self, game: &GameState) -> Solution {
+        // Monster names and images are only used for display, so leave them out of the many
+        // copies made during the search
+        let mut game = game.clone();
+        for text in game.monsters.names.iter_mut().chain(game.monsters.images.iter_mut()) {
+            text.clear();
+        }
+
+        let root = self.search(&game);
+
+        Solution {
+            winnable: root.win_score.is_some(),
+            best_score: game.payments * 3 + game.trophies * 2 + root.score,
+            moves: self.line(&game, |entry| entry.best),
+            winning_moves: self.line(&game, |entry| entry.win),
+            positions: self.memo.len(),
+            complete: !self.incomplete,
+        }
+    }
+
+    /// Rebuild a line of play from `game` by following the command `next` picks from each
+    /// position searched
+    fn line(&self, game: &GameState, next: fn(&Entry) -> Option<Command>) -> Vec<Command> {
+        let mut moves = Vec::new();
+        let mut curr = game.clone();
+        while let Some(command) = self.memo.get(&Position::of(&curr)).and_then(next) {
+            curr.apply(command).unwrap_or_else(|e| {
+                panic!("Solver found an invalid command {:?}: {}\n{}", command, e, curr)
+            });
+            moves.push(command);
+        }
+
+        moves
+    }
+
+    /// Find the best continuation of `game`, memoizing every position searched
+    fn search(&mut self, game: &GameState) -> Entry {
+        let position = Position::of(game);
+        if let Some(entry) = self.memo.get(&position) {
+            return *entry;
+        }
+
+        if game.is_over() {
+            let score = (game.hand.len() + game.deck.len()) as u32;
+            let entry = Entry {
+                score,
+                best: None,
+                win_score: if game.is_won() { Some(score) } else { None },
+                win: None,
+            };
+            self.memo.insert(position, entry);
+            return entry;
+        }
+
+        let mut commands: Vec<Command> = legal_moves(game).iter()
+            .map(|legal| legal.command())
+            .collect();
+        commands.push(Command::EndTurn);
+
+        // Apply every command up front so the most promising ones are searched first. This
+        // only matters when the search is limited.
+        let mut children = Vec::new();
+        for command in commands {
+            let mut next = game.clone();
+            let outcome = match next.apply(command) {
+                Ok(outcome) => outcome,
+                Err(_) => continue
+            };
+
+            // Ending a turn that changes nothing would loop forever
+            if command == Command::EndTurn && Position::of(&next) == position {
+                continue;
+            }
+
+            children.push((command, outcome, next));
+        }
+        children.sort_by_key(|(_, outcome, _)| std::cmp::Reverse((outcome.killed, outcome.trophy)));
+
+        let mut best = Entry { score: 0, best: None, win_score: None, win: None };
+        for (command, outcome, next) in children {
+            // Once the limit is hit, keep the best continuation found so far
+            let exhausted = match self.limit {
+                Some(limit) => self.memo.len() >= limit,
+                None => false
+            };
+
+            if exhausted && best.best.is_some() {
+                self.incomplete = true;
+                break;
+            }
+
+            let child = self.search(&next);
+            let trophy = if outcome.trophy { 2 } else { 0 };
+            let score = child.score + trophy;
+
+            if best.best.is_none() || score > best.score {
+                best.score = score;
+                best.best = Some(command);
+            }
+
+            if let Some(win_score) = child.win_score {
+                let win_score = win_score + trophy;
+                if Some(win_score) > best.win_score {
+                    best.win_score = Some(win_score);
+                    best.win = Some(command);
+                }
+            }
+        }
+
+        self.memo.insert(position, best);
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replay::Replay;
+    use crate::rules::{CardCount, MonsterDef, Rules};
+    use crate::strategy::{play_out, Greedy};
+
+    fn monster(name: &str, strength: u8, to_slay: &[ToSlay]) -> MonsterDef {
+        MonsterDef {
+            name: name.to_string(),
+            strength,
+            ability: None,
+            to_slay: to_slay.to_vec(),
+            image: String::new(),
+        }
+    }
+
+    /// Rules small enough to search every position: two monsters and a deck of eight cards
+    fn small_rules() -> Rules {
+        Rules {
+            monsters: vec![
+                monster("Goblin", 2, &[ToSlay::Melee]),
+                monster("Troll", 3, &[ToSlay::Move, ToSlay::Range]),
+            ],
+            deck: (1..=4).map(|value| CardCount { value, count: 2, image: None }).collect(),
+            dungeon_size: 3,
+            payments: 1,
+            hand_size: 3,
+            ..Rules::default()
+        }
+    }
+
+    /// Apply every command of `moves` to `game`, returning the finished game
+    fn play(game: &GameState, moves: &[Command]) -> GameState {
+        let mut game = game.clone();
+        for &command in moves {
+            game.apply(command).unwrap_or_else(|e| panic!("{:?}: {}\n{}", command, e, game));
+        }
+
+        assert!(game.is_over(), "Line did not finish the game\n{}", game);
+        game
+    }
+
+    /// Solve `game` completely, checking that both lines of the solution are right
+    fn check(game: &GameState) -> Solution {
+        let solution = solve(game);
+        assert!(solution.complete);
+
+        assert_eq!(play(game, &solution.moves).score(), solution.best_score, "\n{}", game);
+        if solution.winnable {
+            assert!(play(game, &solution.winning_moves).is_won(), "\n{}", game);
+        } else {
+            assert!(solution.winning_moves.is_empty());
+        }
+
+        solution
+    }
+
+    #[test]
+    fn best_line_reaches_best_score() {
+        for seed in 0..10 {
+            let game = GameState::with_rules(seed, &small_rules()).unwrap();
+            let solution = check(&game);
+
+            // No strategy can beat the best score
+            let mut greedy = game.clone();
+            play_out(&mut greedy, &mut Greedy, &mut Replay::new(seed)).unwrap();
+            assert!(greedy.score() <= solution.best_score);
+            assert!(!greedy.is_won() || solution.winnable);
+        }
+    }
+
+    #[test]
+    fn reshuffled_games_are_solved() {
+        let rules = Rules {
+            deck: (1..=3).map(|value| CardCount { value, count: 2, image: None }).collect(),
+            reshuffles: 1,
+            ..small_rules()
+        };
+
+        for seed in 0..2 {
+            check(&GameState::with_rules(seed, &rules).unwrap());
+        }
+    }
+
+    #[test]
+    fn discard_order_matters_until_the_last_reshuffle() {
+        let discard = |card| DiscardedCard { card, turn: 1, action: Action::Swap, target: None };
+
+        let mut game = GameState::with_rules(0, &Rules { reshuffles: 1, ..small_rules() }).unwrap();
+        let mut other = game.clone();
+        game.discard_pile = vec![discard(1), discard(2)];
+        other.discard_pile = vec![discard(2), discard(1)];
+        assert_ne!(Position::of(&game), Position::of(&other));
+
+        // The pile is never drawn from again
+        game.reshuffles = 0;
+        other.reshuffles = 0;
+        assert_eq!(Position::of(&game), Position::of(&other));
+    }
+
+    #[test]
+    fn reshuffled_decks_are_told_apart() {
+        // Decks of the same length left by different discard piles
+        let game = GameState::with_rules(0, &small_rules()).unwrap();
+        let mut other = game.clone();
+        other.deck.reverse();
+        assert_ne!(game.deck, other.deck);
+        assert_ne!(Position::of(&game), Position::of(&other));
+    }
+
+    #[test]
+    fn limited_search_still_finishes_a_line() {
+        let game = GameState::new(0);
+        let solution = solve_with_limit(&game, 1000);
+        assert!(!solution.complete);
+        assert_eq!(play(&game, &solution.moves).score(), solution.best_score);
+    }
+}
@@ -0,0 +1,380 @@
+//! Lifetime statistics over every game played, accumulated on disk from the events each game
+//! emits
+
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use crate::engine::*;
+use crate::replay::{Replay, ReplayError};
+use crate::rules::Difficulty;
+use crate::util::ratio;
+
+/// Errors found while reading or writing the statistics file
+#[derive(Debug)]
+pub enum StatsError {
+    /// Failed to read or write the statistics file
+    Io(io::Error),
+
+    /// Statistics file is not valid
+    Parse(serde_json::Error),
+
+    /// The plays of a finished game could not be re-applied from its replay
+    Replay(ReplayError),
+}
+
+impl fmt::Display for StatsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StatsError::Io(e) => write!(f, "Statistics IO error: {}", e),
+            StatsError::Parse(e) => write!(f, "Invalid statistics: {}", e),
+            StatsError::Replay(e) => write!(f, "Cannot count the plays of the game: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StatsError {}
+
+impl From<io::Error> for StatsError {
+    fn from(e: io::Error) -> StatsError {
+        StatsError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StatsError {
+    fn from(e: serde_json::Error) -> StatsError {
+        StatsError::Parse(e)
+    }
+}
+
+impl From<ReplayError> for StatsError {
+    fn from(e: ReplayError) -> StatsError {
+        StatsError::Replay(e)
+    }
+}
+
+/// Something that happened during a game that the lifetime statistics count
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameEvent {
+    /// A new game was started
+    Started {
+        seed: u64,
+        difficulty: Difficulty,
+    },
+
+    /// A card was played for the action, or the turn was ended
+    Played(Action),
+
+    /// The player transformed into the Monstrous form
+    Transformed,
+
+    /// The game is over
+    Finished {
+        won: bool,
+        score: u32,
+        trophies: u32,
+
+        /// Names of the monsters still alive at the end
+        survivors: Vec<String>,
+    },
+}
+
+impl GameEvent {
+    /// Event of starting the given `game`
+    pub fn started(game: &GameState) -> GameEvent {
+        GameEvent::Started {
+            seed: game.seed,
+            difficulty: game.difficulty,
+        }
+    }
+
+    /// Events emitted by the `command` that was applied to `game` with the given `outcome`
+    pub fn applied(game: &GameState, command: Command, outcome: &Outcome) -> Vec<GameEvent> {
+        let action = match command {
+            Command::Play(action, _) => action,
+            Command::EndTurn => Action::EndTurn,
+        };
+
+        let mut events = vec![GameEvent::Played(action)];
+        if outcome.transformed {
+            events.push(GameEvent::Transformed);
+        }
+
+        if outcome.game_over {
+            let survivors = game.monsters.monster_slots()
+                .filter(|&index| game.monsters.alive[index])
+                .map(|index| game.monsters.names[index].clone())
+                .collect();
+
+            events.push(GameEvent::Finished {
+                won: game.is_won(),
+                score: game.score(),
+                trophies: game.trophies,
+                survivors,
+            });
+        }
+
+        events
+    }
+
+    /// Events emitted by every command recorded in `replay`, in order. Plays that were undone
+    /// are not in the replay, so they are never counted.
+    pub fn replayed(replay: &Replay) -> Result<Vec<GameEvent>, ReplayError> {
+        let mut game = GameState::with_rules(replay.seed, &replay.rules)
+            .map_err(ReplayError::Setup)?;
+
+        let mut events = Vec::new();
+        for (index, entry) in replay.entries.iter().enumerate() {
+            let outcome = game.apply(entry.command)
+                .map_err(|error| ReplayError::Rule { index, error })?;
+            events.extend(GameEvent::applied(&game, entry.command, &outcome));
+        }
+
+        Ok(events)
+    }
+}
+
+/// Totals over every game played, as stored in the statistics file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LifetimeStats {
+    /// Games started
+    pub started: u32,
+
+    /// Games played until the end
+    pub finished: u32,
+
+    /// Finished games where every monster was slain
+    pub wins: u32,
+
+    /// Sum of the final scores of every finished game
+    pub total_score: u64,
+
+    /// Sum of the trophies gathered in every finished game
+    pub total_trophies: u64,
+
+    /// Games where the player became Monstrous
+    pub transformations: u32,
+
+    /// Number of finished games each monster survived until the end, by name
+    pub survivors: BTreeMap<String, u32>,
+
+    /// Number of times each kind of action was used, by `Action::name`
+    pub actions: BTreeMap<String, u32>,
+}
+
+impl LifetimeStats {
+    /// Read the statistics from `path`. Returns empty statistics if there is no file yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<LifetimeStats, StatsError> {
+        let data = match fs::read_to_string(path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(LifetimeStats::default()),
+            Err(e) => return Err(e.into()),
+        };
+
+        Ok(serde_json::from_str(&data)?)
+    }
+
+    /// Write the statistics to `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), StatsError> {
+        fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Add the given `events` to the statistics in the file at `path`
+    pub fn accumulate(path: impl AsRef<Path>, events: &[GameEvent]) -> Result<(), StatsError> {
+        let path = path.as_ref();
+        let mut stats = LifetimeStats::load(path)?;
+        for event in events {
+            stats.record(event);
+        }
+
+        stats.save(path)
+    }
+
+    /// Add every play of the finished game recorded in `replay` to the statistics in the file at
+    /// `path` at once, so plays that were undone are never counted
+    pub fn accumulate_game(path: impl AsRef<Path>, replay: &Replay) -> Result<(), StatsError> {
+        LifetimeStats::accumulate(path, &GameEvent::replayed(replay)?)
+    }
+
+    /// Count a single `event`
+    pub fn record(&mut self, event: &GameEvent) {
+        match event {
+            GameEvent::Started { .. } => self.started += 1,
+            GameEvent::Played(action) => {
+                *self.actions.entry(action.name().to_string()).or_insert(0) += 1;
+            }
+            GameEvent::Transformed => self.transformations += 1,
+            GameEvent::Finished { won, score, trophies, survivors } => {
+                self.finished += 1;
+                self.wins += *won as u32;
+                self.total_score += *score as u64;
+                self.total_trophies += *trophies as u64;
+
+                for name in survivors {
+                    *self.survivors.entry(name.clone()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    /// Fraction of the finished games that were won
+    pub fn win_rate(&self) -> f64 {
+        ratio(self.wins, self.finished)
+    }
+
+    /// Mean final score of the finished games
+    pub fn average_score(&self) -> f64 {
+        ratio(self.total_score, self.finished)
+    }
+
+    /// Mean number of trophies gathered per finished game
+    pub fn trophies_per_game(&self) -> f64 {
+        ratio(self.total_trophies, self.finished)
+    }
+
+    /// Fraction of the finished games where the player became Monstrous. Transformations are
+    /// only counted once a game is over, so abandoned games are left out.
+    pub fn transformation_rate(&self) -> f64 {
+        ratio(self.transformations, self.finished)
+    }
+
+    /// The `count` monsters that survived the most games, most often first
+    pub fn top_survivors(&self, count: usize) -> Vec<(&str, u32)> {
+        let mut survivors: Vec<(&str, u32)> = self.survivors.iter()
+            .map(|(name, games)| (name.as_str(), *games))
+            .collect();
+
+        // The sort is stable, so ties stay sorted by name
+        survivors.sort_by_key(|(_, games)| std::cmp::Reverse(*games));
+        survivors.truncate(count);
+        survivors
+    }
+
+    /// Human readable summary of the statistics, listing the `survivors` monsters that survived
+    /// the most games
+    pub fn to_text(&self, survivors: usize) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Games started:     {}", self.started);
+        let _ = writeln!(out, "Games finished:    {}", self.finished);
+        let _ = writeln!(out, "Win rate:          {:.1}%", self.win_rate() * 100.0);
+        let _ = writeln!(out, "Average score:     {:.2}", self.average_score());
+        let _ = writeln!(out, "Trophies per game: {:.2}", self.trophies_per_game());
+        let _ = writeln!(out, "Became Monstrous:  {:.1}% of games",
+                         self.transformation_rate() * 100.0);
+
+        let _ = writeln!(out, "\nMost frequent survivors:");
+        for (name, games) in self.top_survivors(survivors) {
+            let _ = writeln!(out, "  {:<12} {:>6} games", name, games);
+        }
+
+        let _ = writeln!(out, "\nActions used:");
+        for (name, count) in &self.actions {
+            let _ = writeln!(out, "  {:<12} {:>6}", name, count);
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::strategy::{play_out, Greedy};
+
+    fn finished(won: bool, score: u32, trophies: u32, survivors: &[&str]) -> GameEvent {
+        GameEvent::Finished {
+            won,
+            score,
+            trophies,
+            survivors: survivors.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn record_counts_every_event() {
+        let mut stats = LifetimeStats::default();
+        let started = GameEvent::Started { seed: 1, difficulty: Difficulty::Normal };
+
+        for event in &[
+            started.clone(),
+            GameEvent::Played(Action::Swap),
+            GameEvent::Played(Action::Melee(Entity::Character)),
+            GameEvent::Played(Action::Melee(Entity::Companion)),
+            GameEvent::Transformed,
+            finished(true, 20, 4, &[]),
+            started.clone(),
+            finished(false, 6, 1, &["Goblin", "Troll"]),
+            started,
+            finished(false, 3, 0, &["Troll"]),
+        ] {
+            stats.record(event);
+        }
+
+        assert_eq!(stats.started, 3);
+        assert_eq!(stats.finished, 3);
+        assert_eq!(stats.wins, 1);
+        assert_eq!(stats.total_score, 29);
+        assert_eq!(stats.total_trophies, 5);
+        assert_eq!(stats.transformations, 1);
+        assert_eq!(stats.actions["Melee"], 2);
+        assert_eq!(stats.actions["Swap"], 1);
+        assert_eq!(stats.top_survivors(5), vec![("Troll", 2), ("Goblin", 1)]);
+    }
+
+    #[test]
+    fn rates_over_games() {
+        let mut stats = LifetimeStats::default();
+        assert_eq!(stats.win_rate(), 0.0);
+        assert_eq!(stats.average_score(), 0.0);
+        assert_eq!(stats.trophies_per_game(), 0.0);
+        assert_eq!(stats.transformation_rate(), 0.0);
+
+        stats.started = 4;
+        stats.finished = 2;
+        stats.wins = 1;
+        stats.total_score = 25;
+        stats.total_trophies = 3;
+        stats.transformations = 1;
+
+        assert_eq!(stats.win_rate(), 0.5);
+        assert_eq!(stats.average_score(), 12.5);
+        assert_eq!(stats.trophies_per_game(), 1.5);
+        assert_eq!(stats.transformation_rate(), 0.5);
+    }
+
+    #[test]
+    fn abandoned_games_do_not_lower_the_rates() {
+        let mut stats = LifetimeStats::default();
+        let started = GameEvent::Started { seed: 1, difficulty: Difficulty::Normal };
+
+        // The second game is abandoned, so none of its plays are ever counted
+        for event in &[started.clone(), GameEvent::Transformed, finished(true, 20, 4, &[]), started] {
+            stats.record(event);
+        }
+
+        assert_eq!(stats.started, 2);
+        assert_eq!(stats.win_rate(), 1.0);
+        assert_eq!(stats.transformation_rate(), 1.0);
+    }
+
+    #[test]
+    fn replayed_events_match_the_game() {
+        let mut game = GameState::new(3);
+        let mut replay = Replay::new(3);
+        play_out(&mut game, &mut Greedy, &mut replay).expect("Greedy played an invalid command");
+
+        let events = GameEvent::replayed(&replay).expect("Replay was rejected");
+        let played = events.iter().filter(|event| matches!(event, GameEvent::Played(_))).count();
+        assert_eq!(played, replay.entries.len());
+
+        match events.last() {
+            Some(GameEvent::Finished { score, .. }) => assert_eq!(*score, game.score()),
+            last => panic!("Game did not finish: {:?}\n{}", last, game),
+        }
+    }
+}
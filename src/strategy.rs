@@ -0,0 +1,311 @@
+//! Computer players choosing the commands of a game
+
+use std::collections::VecDeque;
+
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use crate::consts::*;
+use crate::engine::*;
+use crate::moves::legal_moves;
+use crate::replay::Replay;
+
+/// Names of the built-in strategies, as accepted by `by_name`
+pub const STRATEGIES: [&str; 3] = ["greedy", "random", "montecarlo"];
+
+/// A player choosing the next command from the current state of the game
+pub trait Strategy {
+    /// Name of the strategy
+    fn name(&self) -> &'static str;
+
+    /// Choose the next command to apply to `game`, or `None` if the strategy has no commands
+    /// left. Only called while the game is not over.
+    fn choose(&mut self, game: &GameState) -> Option<Command>;
+}
+
+/// Create the built-in strategy called `name`, seeding any random choices from `seed`
+pub fn by_name(name: &str, seed: u64) -> Option<Box<dyn Strategy>> {
+    match name {
+        "greedy" => Some(Box::new(Greedy)),
+        "random" => Some(Box::new(Random::new(seed))),
+        "montecarlo" => Some(Box::new(MonteCarlo::new(seed, MONTE_CARLO_ROLLOUTS))),
+        _ => None
+    }
+}
+
+/// Let `strategy` play `game` until it is over or the strategy runs out of commands, recording
+/// every command into `replay`. The final score is only recorded if the game is over.
+pub fn play_out(game: &mut GameState, strategy: &mut dyn Strategy, replay: &mut Replay)
+        -> Result<(), RuleError> {
+    while !game.is_over() {
+        let turn = game.turn;
+        let command = match strategy.choose(game) {
+            Some(command) => command,
+            None => return Ok(())
+        };
+
+        let outcome = game.apply(command)?;
+        replay.record(turn, command, &outcome);
+    }
+
+    replay.finish(game);
+    Ok(())
+}
+
+/// Every command worth considering in the current position: each legal move, plus ending the
+/// turn if that draws new cards and no Noxious discard is pending. A turn always starts with a
+/// full hand, so every turn spends at least one card and a game built from these commands always
+/// ends.
+pub fn candidates(game: &GameState) -> Vec<Command> {
+    let mut commands: Vec<Command> = legal_moves(game).iter()
+        .map(|legal| legal.command())
+        .collect();
+
+    let can_draw = !game.deck.is_empty() || game.can_reshuffle();
+    if !game.discard_pending && can_draw && game.hand.len() < game.hand_limit as usize {
+        commands.push(Command::EndTurn);
+    }
+
+    commands
+}
+
+/// Plays whichever command looks best right now, without looking further ahead
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Greedy;
+
+impl Greedy {
+    /// Rough value of applying `command` to `game`. Slaying a monster is worth the most, then
+    /// gaining a trophy and hitting a monster. Every card spent costs a point of the final
+    /// score, so plays that achieve nothing are negative.
+    fn evaluate(game: &GameState, command: Command) -> i32 {
+        let mut next = game.clone();
+        let outcome = match next.apply(command) {
+            Ok(outcome) => outcome,
+            Err(_) => return i32::MIN
+        };
+
+        let mut value = 0;
+
+        if outcome.killed {
+            value += 10;
+        }
+
+        if outcome.trophy {
+            value += 4;
+        }
+
+        if outcome.hit.is_some() {
+            value += 3;
+        }
+
+        if outcome.card.is_some() {
+            value -= 2;
+        }
+
+        if outcome.noxious_discard.is_some() {
+            value -= 2;
+        }
+
+        // Moving or swapping is only worth it if it sets up an attack
+        if let Command::Play(Action::Move(..), _) | Command::Play(Action::Swap, _) = command {
+            let attacks = legal_moves(&next).iter()
+                .any(|legal| matches!(legal.action, Action::Range(..) | Action::Melee(_)));
+
+            if attacks {
+                value += 1;
+            }
+        }
+
+        value
+    }
+}
+
+impl Strategy for Greedy {
+    fn name(&self) -> &'static str {
+        "greedy"
+    }
+
+    fn choose(&mut self, game: &GameState) -> Option<Command> {
+        let mut best = None;
+
+        for command in candidates(game) {
+            let value = Greedy::evaluate(game, command);
+            match best {
+                Some((_, best_value)) if best_value >= value => {}
+                _ => best = Some((command, value))
+            }
+        }
+
+        Some(best.map(|(command, _)| command).unwrap_or(Command::EndTurn))
+    }
+}
+
+/// Plays a random command worth considering
+#[derive(Debug, Clone)]
+pub struct Random {
+    /// Generator choosing the commands
+    rng: ChaCha8Rng,
+}
+
+impl Random {
+    pub fn new(seed: u64) -> Random {
+        Random {
+            rng: ChaCha8Rng::seed_from_u64(seed),
+        }
+    }
+}
+
+impl Strategy for Random {
+    fn name(&self) -> &'static str {
+        "random"
+    }
+
+    fn choose(&mut self, game: &GameState) -> Option<Command> {
+        Some(candidates(game).choose(&mut self.rng).copied().unwrap_or(Command::EndTurn))
+    }
+}
+
+/// Plays a fixed list of commands in order, such as the commands of a replay or a script, and
+/// stops once they run out
+#[derive(Debug, Clone)]
+pub struct Scripted {
+    commands: VecDeque<Command>,
+}
+
+impl Scripted {
+    pub fn new(commands: Vec<Command>) -> Scripted {
+        Scripted { commands: commands.into() }
+    }
+}
+
+impl Strategy for Scripted {
+    fn name(&self) -> &'static str {
+        "scripted"
+    }
+
+    fn choose(&mut self, _game: &GameState) -> Option<Command> {
+        self.commands.pop_front()
+    }
+}
+
+/// Plays the command with the best average final score over a number of random games played
+/// out from it
+#[derive(Debug, Clone)]
+pub struct MonteCarlo {
+    /// Number of random games played out for each command
+    rollouts: usize,
+
+    /// Player of the random games
+    random: Random,
+}
+
+impl MonteCarlo {
+    pub fn new(seed: u64, rollouts: usize) -> MonteCarlo {
+        MonteCarlo {
+            rollouts,
+            random: Random::new(seed),
+        }
+    }
+
+    /// Final score of a random game played out from `game`
+    fn rollout(&mut self, mut game: GameState) -> u32 {
+        while let Some(command) = self.random.choose(&game) {
+            if game.is_over() || game.apply(command).is_err() {
+                break;
+            }
+        }
+
+        game.score()
+    }
+}
+
+impl Strategy for MonteCarlo {
+    fn name(&self) -> &'static str {
+        "montecarlo"
+    }
+
+    fn choose(&mut self, game: &GameState) -> Option<Command> {
+        let commands = candidates(game);
+
+        // Nothing to compare
+        if commands.len() == 1 {
+            return Some(commands[0]);
+        }
+
+        let mut best = None;
+
+        for command in commands {
+            let mut next = game.clone();
+            if next.apply(command).is_err() {
+                continue;
+            }
+
+            let total: u32 = (0..self.rollouts).map(|_| self.rollout(next.clone())).sum();
+
+            match best {
+                Some((_, best_total)) if best_total >= total => {}
+                _ => best = Some((command, total))
+            }
+        }
+
+        Some(best.map(|(command, _)| command).unwrap_or(Command::EndTurn))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::tests::{board, plain};
+
+    /// Commands `strategy` plays in the game of `seed` until it is over
+    fn play(seed: u64, strategy: &mut dyn Strategy) -> Vec<Command> {
+        let mut game = GameState::new(seed);
+        let mut replay = Replay::new(seed);
+        play_out(&mut game, strategy, &mut replay).unwrap();
+        assert!(game.is_over(), "{} stopped early\n{}", strategy.name(), game);
+        assert!(replay.score.is_some());
+        replay.entries.iter().map(|entry| entry.command).collect()
+    }
+
+    #[test]
+    fn greedy_slays_rather_than_moves() {
+        let game = board(&[plain(2, &[ToSlay::Range]), plain(3, &[ToSlay::Melee])], &[1, 2, 3]);
+        let command = Greedy.choose(&game).unwrap();
+        assert_eq!(command, Command::Play(Action::Range(Entity::Character, Direction::Right), 0));
+
+        let mut next = game.clone();
+        assert!(next.apply(command).unwrap().killed, "\n{}", game);
+    }
+
+    #[test]
+    fn random_play_repeats_for_a_seed() {
+        assert_eq!(play(5, &mut Random::new(9)), play(5, &mut Random::new(9)));
+    }
+
+    #[test]
+    fn monte_carlo_finishes_a_game() {
+        assert!(!play(5, &mut MonteCarlo::new(9, 2)).is_empty());
+    }
+
+    #[test]
+    fn every_strategy_finishes_its_games() {
+        for &name in STRATEGIES.iter() {
+            for seed in 0..3 {
+                play(seed, by_name(name, seed).unwrap().as_mut());
+            }
+        }
+    }
+
+    #[test]
+    fn scripted_play_stops_when_the_commands_run_out() {
+        let mut game = GameState::new(3);
+        let mut replay = Replay::new(3);
+        let commands = vec![Command::Play(Action::Swap, 0), Command::EndTurn];
+
+        play_out(&mut game, &mut Scripted::new(commands.clone()), &mut replay).unwrap();
+        assert!(!game.is_over());
+        assert_eq!(replay.entries.iter().map(|entry| entry.command).collect::<Vec<_>>(), commands);
+        assert_eq!(replay.score, None);
+    }
+}
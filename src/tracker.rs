@@ -0,0 +1,186 @@
+//! What the player can know about the cards left to draw.
+//!
+//! The payment cards are withheld face down at the beginning of the game, so the cards that have
+//! not been seen yet are the draw deck and the payments together. Every unseen card is equally
+//! likely to be in the draw deck.
+//!
+//! Once the discard pile has been shuffled back in, the draw deck only holds cards that were seen
+//! in the discard pile, so its contents are known exactly and the payments cannot be in it. When
+//! the deck runs out while the hand is refilled and a reshuffle is left, the rest of the refill
+//! comes from the discard pile.
+
+use crate::engine::GameState;
+use crate::rules::Rules;
+use crate::util::ratio;
+
+/// Odds of drawing a single card value
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CardOdds {
+    /// Value of the card
+    pub value: u8,
+
+    /// Cards of this value that can be in the draw deck: not seen yet, in the draw deck or
+    /// withheld as payments, or only those in the draw deck once it has been reshuffled
+    pub unseen: u32,
+
+    /// Expected number of cards of this value left in the draw deck
+    pub expected: f64,
+
+    /// Probability that the next card drawn has this value
+    pub next_draw: f64,
+
+    /// Probability that at least one card of this value is drawn when the hand is refilled
+    pub refill: f64,
+}
+
+/// Remaining cards of every value in the deck recipe, as far as the player can tell
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeckTracker {
+    /// Cards left in the draw deck
+    pub deck_size: usize,
+
+    /// Cards withheld as payments
+    pub withheld: usize,
+
+    /// The draw deck was reshuffled from the discard pile, so its contents are known
+    pub reshuffled: bool,
+
+    /// Cards drawn if the turn ended now, including those drawn from a reshuffled discard pile
+    pub refill_size: usize,
+
+    /// Odds of every card value in the deck recipe, lowest value first
+    pub cards: Vec<CardOdds>,
+}
+
+impl DeckTracker {
+    /// Track the cards left to draw in `game`, which was created with `rules`
+    pub fn new(game: &GameState, rules: &Rules) -> DeckTracker {
+        let deck_size = game.deck.len();
+        let withheld = game.withheld.len();
+        let reshuffled = game.reshuffles < rules.reshuffles;
+
+        // Cards the draw deck is made from, as far as the player can tell
+        let pool: Vec<u8> = match reshuffled {
+            true => game.deck.clone(),
+            false => game.deck.iter().chain(&game.withheld).copied().collect(),
+        };
+
+        // Cards the refill draws from the discard pile once the deck runs out
+        let discards: Vec<u8> = match game.reshuffles {
+            0 => Vec::new(),
+            _ => game.discard_pile.iter().map(|discarded| discarded.card).collect(),
+        };
+
+        let wanted = (game.hand_limit as usize).saturating_sub(game.hand.len());
+        let from_deck = wanted.min(deck_size);
+        let from_discards = (wanted - from_deck).min(discards.len());
+
+        let mut values: Vec<u8> = rules.deck.iter().map(|card| card.value).collect();
+        values.sort_unstable();
+        values.dedup();
+
+        let cards = values.into_iter().map(|value| {
+            let unseen = pool.iter().filter(|&&card| card == value).count();
+            let discarded = discards.iter().filter(|&&card| card == value).count();
+
+            // The next card comes from the discard pile only once the deck is empty
+            let next_draw = match deck_size {
+                0 => ratio(discarded, discards.len()),
+                _ => ratio(unseen, pool.len()),
+            };
+
+            // Missing the value in the deck and then in the reshuffled discard pile
+            let miss = (1.0 - draw_at_least_one(unseen, pool.len(), from_deck))
+                * (1.0 - draw_at_least_one(discarded, discards.len(), from_discards));
+
+            CardOdds {
+                value,
+                unseen: unseen as u32,
+                expected: ratio(unseen * deck_size, pool.len()),
+                next_draw,
+                refill: 1.0 - miss,
+            }
+        }).collect();
+
+        DeckTracker {
+            deck_size,
+            withheld,
+            reshuffled,
+            refill_size: from_deck + from_discards,
+            cards,
+        }
+    }
+}
+
+/// Probability of drawing at least one of `matching` cards when drawing `draws` cards out of
+/// `total` shuffled cards
+fn draw_at_least_one(matching: usize, total: usize, draws: usize) -> f64 {
+    // Probability of missing every matching card with each draw in turn
+    let mut miss = 1.0;
+    for i in 0..draws.min(total) {
+        miss *= ratio((total - matching).saturating_sub(i), total - i);
+    }
+
+    1.0 - miss
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{Action, DiscardedCard};
+
+    /// Game of the given `rules` holding the given cards and an empty hand
+    fn game(rules: &Rules, deck: &[u8], withheld: &[u8], discards: &[u8]) -> GameState {
+        let mut game = GameState::with_rules(0, rules).unwrap();
+        game.deck = deck.to_vec();
+        game.withheld = withheld.to_vec();
+        game.discard_pile = discards.iter()
+            .map(|&card| DiscardedCard { card, turn: 1, action: Action::Swap, target: None })
+            .collect();
+        game.hand.clear();
+        game
+    }
+
+    fn odds(tracker: &DeckTracker, value: u8) -> CardOdds {
+        *tracker.cards.iter().find(|card| card.value == value).unwrap()
+    }
+
+    #[test]
+    fn payments_may_be_in_the_deck_until_it_is_reshuffled() {
+        let rules = Rules { reshuffles: 1, ..Rules::default() };
+        let mut game = game(&rules, &[1, 2], &[5, 5], &[]);
+
+        let tracker = DeckTracker::new(&game, &rules);
+        assert!(!tracker.reshuffled);
+        assert_eq!(odds(&tracker, 5).unseen, 2);
+        assert_eq!(odds(&tracker, 5).next_draw, 0.5);
+        assert_eq!(odds(&tracker, 5).expected, 1.0);
+
+        // The reshuffled deck was seen in the discard pile, so the payments cannot be in it
+        game.reshuffles = 0;
+        let tracker = DeckTracker::new(&game, &rules);
+        assert!(tracker.reshuffled);
+        assert_eq!(odds(&tracker, 5).unseen, 0);
+        assert_eq!(odds(&tracker, 5).next_draw, 0.0);
+        assert_eq!(odds(&tracker, 1).next_draw, 0.5);
+        assert_eq!(odds(&tracker, 1).expected, 1.0);
+    }
+
+    #[test]
+    fn refill_continues_from_the_discard_pile() {
+        let rules = Rules { reshuffles: 1, ..Rules::default() };
+        let mut game = game(&rules, &[1], &[5], &[3, 3, 4]);
+
+        let tracker = DeckTracker::new(&game, &rules);
+        assert_eq!(tracker.refill_size, 4);
+        assert_eq!(odds(&tracker, 3).refill, 1.0);
+        assert_eq!(odds(&tracker, 1).refill, 0.5);
+        assert_eq!(odds(&tracker, 2).refill, 0.0);
+
+        // Without a reshuffle left, the refill stops at the end of the deck
+        game.reshuffles = 0;
+        let tracker = DeckTracker::new(&game, &Rules::default());
+        assert_eq!(tracker.refill_size, 1);
+        assert_eq!(odds(&tracker, 3).refill, 0.0);
+    }
+}
@@ -0,0 +1,28 @@
+//! Small helpers shared by the statistics modules
+
+/// Counts that can be turned into a fraction
+pub(crate) trait Count: Copy {
+    fn to_f64(self) -> f64;
+}
+
+macro_rules! impl_count {
+    ($($t:ty),*) => {
+        $(impl Count for $t {
+            fn to_f64(self) -> f64 {
+                self as f64
+            }
+        })*
+    };
+}
+
+impl_count!(u32, u64, usize);
+
+/// `part / whole`, or 0 if `whole` is 0
+pub(crate) fn ratio(part: impl Count, whole: impl Count) -> f64 {
+    let whole = whole.to_f64();
+    if whole == 0.0 {
+        return 0.0;
+    }
+
+    part.to_f64() / whole
+}